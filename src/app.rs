@@ -2,11 +2,13 @@ use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom,
     rc::Rc,
+    time::Duration,
 };
 
 use crate::{
-    pages::{Content, ContentFeed, Home, LivePage, Settings, Start},
-    utils::{IpfsService, LocalStorage, Web3Service},
+    components::{ChatLimits, EmotePack, EMOTE_PACK_PATH},
+    pages::{Compose, Content, ContentFeed, Home, LivePage, Settings, Start},
+    utils::{CancelToken, IpfsError, IpfsService, LocalStorage, Web3Service},
 };
 
 use wasm_bindgen_futures::spawn_local;
@@ -37,9 +39,13 @@ use either::Either;
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
+type Web3Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 type CallbackResult<T> = (Cid, Cid, Result<(Cid, T)>);
 
+/// IPNS links of every beacon are resolved again this often.
+const IPNS_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Switch, Debug, Clone, PartialEq)]
 pub enum AppRoute {
     #[to = "/#/content/{cid}"]
@@ -54,6 +60,9 @@ pub enum AppRoute {
     #[to = "/#/feed"]
     Feed,
 
+    #[to = "/#/compose"]
+    Compose,
+
     #[to = "/#/start"]
     Start,
 
@@ -66,10 +75,13 @@ pub struct App {
 
     peer_id: Option<PeerId>,
     peer_id_cb: Callback<Result<PeerId>>,
+    ipfs_error: Option<IpfsError>,
+    read_only: bool,
 
-    name_cb: Callback<(String, Result<Cid>)>,
+    name_cb: Callback<(String, Web3Result<Cid>)>,
 
     beacon_set: HashSet<Cid>,
+    beacon_cid: Option<Cid>,
     beacon: Option<Rc<Beacon>>,
     beacon_cb: Callback<(Cid, Result<Beacon>)>,
 
@@ -87,6 +99,15 @@ pub struct App {
 
     content: Rc<ContentCache>,
 
+    /// Maps beacons to their ENS name, comment signatures are checked against them.
+    ens_names: Rc<HashMap<Cid, String>>,
+    /// Maps beacons to their display name.
+    display_names: HashMap<Cid, String>,
+    /// Maps comments to the beacon listing them, none when listed by many.
+    commenters: Rc<HashMap<Cid, Option<Cid>>>,
+    /// Display names of your friends.
+    friend_names: Rc<HashSet<String>>,
+
     friends_cid: Option<Cid>,
     friends: Rc<Friendlies>,
     friends_cb: Callback<CallbackResult<Friendlies>>,
@@ -94,6 +115,10 @@ pub struct App {
     live_cid: Option<Cid>,
     live: Rc<Live>,
     live_cb: Callback<CallbackResult<Live>>,
+    limits: Rc<ChatLimits>,
+    limits_cb: Callback<Result<ChatLimits>>,
+    emotes: Rc<EmotePack>,
+    emotes_cb: Callback<Result<EmotePack>>,
 
     bans_cid: Option<Cid>,
     bans: Rc<Bans>,
@@ -102,16 +127,24 @@ pub struct App {
     mods_cid: Option<Cid>,
     mods: Rc<Moderators>,
     mods_cb: Callback<CallbackResult<Moderators>>,
+
+    /// IPNS links watched for updates.
+    watched: Vec<(Cid, Callback<Cid>)>,
+    /// Cancel the IPNS poll loop.
+    watch_token: CancelToken,
 }
 
 #[allow(clippy::large_enum_variant)]
 pub enum AppMsg {
     PeerID(Result<PeerId>),
-    ENSResolve((String, Result<Cid>)),
+    ReadOnly(bool),
+    ENSResolve((String, Web3Result<Cid>)),
     Beacon((Cid, Result<Beacon>)),
     Identity(CallbackResult<Identity>),
     Feed(CallbackResult<FeedAnchor>),
     Live(CallbackResult<Live>),
+    Limits(Result<ChatLimits>),
+    Emotes(Result<EmotePack>),
     Comments(CallbackResult<Commentary>),
     Friends(CallbackResult<Friendlies>),
     Bans(CallbackResult<Bans>),
@@ -136,10 +169,13 @@ impl Component for App {
 
             peer_id: None,
             peer_id_cb: link.callback(AppMsg::PeerID),
+            ipfs_error: None,
+            read_only: false,
 
             name_cb: link.callback(AppMsg::ENSResolve),
 
             beacon_set: HashSet::with_capacity(10),
+            beacon_cid: None,
             beacon: None,
             beacon_cb: link.callback(AppMsg::Beacon),
 
@@ -154,9 +190,18 @@ impl Component for App {
 
             content: Rc::from(ContentCache::create()),
 
+            ens_names: Rc::from(HashMap::with_capacity(10)),
+            display_names: HashMap::with_capacity(10),
+            commenters: Rc::from(HashMap::with_capacity(100)),
+            friend_names: Rc::from(HashSet::with_capacity(10)),
+
             live_cid: None,
             live: Rc::from(Live::default()),
             live_cb: link.callback(AppMsg::Live),
+            limits: Rc::from(ChatLimits::default()),
+            limits_cb: link.callback(AppMsg::Limits),
+            emotes: Rc::from(EmotePack::default()),
+            emotes_cb: link.callback(AppMsg::Emotes),
 
             bans_cid: None,
             bans: Rc::from(Bans::default()),
@@ -169,8 +214,13 @@ impl Component for App {
             friends_cid: None,
             friends: Rc::from(Friendlies::default()),
             friends_cb: link.callback(AppMsg::Friends),
+
+            watched: Vec::with_capacity(20),
+            watch_token: CancelToken::new(),
         };
 
+        app.props.ipfs.on_read_only(link.callback(AppMsg::ReadOnly));
+
         app.check_ipfs();
         app.get_beacon(app.props.beacon);
 
@@ -180,11 +230,14 @@ impl Component for App {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             AppMsg::PeerID(result) => self.on_peer_id(result),
+            AppMsg::ReadOnly(read_only) => self.on_read_only(read_only),
             AppMsg::ENSResolve(result) => self.on_name(result),
             AppMsg::Beacon(result) => self.on_beacon(result),
             AppMsg::Identity(result) => self.on_identity(result),
             AppMsg::Feed(result) => self.on_feed(result),
             AppMsg::Live(result) => self.on_live(result),
+            AppMsg::Limits(result) => self.on_chat_limits(result),
+            AppMsg::Emotes(result) => self.on_emotes(result),
             AppMsg::Comments(result) => self.on_comments(result),
             AppMsg::Friends(result) => self.on_friends(result),
             AppMsg::Bans(result) => self.on_ban_list(result),
@@ -198,25 +251,34 @@ impl Component for App {
 
     fn view(&self) -> Html {
         let peer_id = self.peer_id;
+        let error = self.ipfs_error.clone();
+        let read_only = self.read_only;
         let web3 = self.props.web3.clone();
         let ipfs = self.props.ipfs.clone();
         let storage = self.props.storage.clone();
         let content = self.content.clone();
-        //let beacon = self.beacon.clone().unwrap_or_default();
+        let ens_names = self.ens_names.clone();
+        let commenters = self.commenters.clone();
+        let beacon_cid = self.beacon_cid;
+        let friend_names = self.friend_names.clone();
+        let beacon = self.beacon.clone();
         let bans = self.bans.clone();
         let mods = self.mods.clone();
         let live = self.live.clone();
-        //let friends = self.friends.clone();
+        let limits = self.limits.clone();
+        let emotes = self.emotes.clone();
+        let friends = self.friends.clone();
 
         html! {
             <>
                 <Router<AppRoute>
                     render = Router::render(move |switch: AppRoute| {
                         match switch {
-                            AppRoute::Content(cid) => html! { <Content ipfs=ipfs.clone() cid=cid content=content.clone() /> },
-                            AppRoute::Settings => html! { <Settings storage=storage.clone() peer_id=peer_id /> },
-                            AppRoute::Live => html! { <LivePage peer_id=peer_id ipfs=ipfs.clone() web3=web3.clone() storage=storage.clone() live=live.clone() bans=bans.clone() mods=mods.clone() /> },
-                            AppRoute::Feed => html! { <ContentFeed ipfs=ipfs.clone() storage=storage.clone() content=content.clone() peer_id=peer_id /> },
+                            AppRoute::Content(cid) => html! { <Content ipfs=ipfs.clone() web3=web3.clone() storage=storage.clone() cid=cid content=content.clone() ens_names=ens_names.clone() commenters=commenters.clone() friend_names=friend_names.clone() beacon_cid=beacon_cid beacon=beacon.clone() /> },
+                            AppRoute::Settings => html! { <Settings ipfs=ipfs.clone() storage=storage.clone() peer_id=peer_id error=error.clone() /> },
+                            AppRoute::Live => html! { <LivePage peer_id=peer_id error=error.clone() read_only=read_only ipfs=ipfs.clone() web3=web3.clone() storage=storage.clone() beacon=beacon.clone() live=live.clone() limits=limits.clone() emotes=emotes.clone() friends=friends.clone() bans=bans.clone() mods=mods.clone() /> },
+                            AppRoute::Feed => html! { <ContentFeed ipfs=ipfs.clone() storage=storage.clone() content=content.clone() peer_id=peer_id error=error.clone() read_only=read_only /> },
+                            AppRoute::Compose => html! { <Compose ipfs=ipfs.clone() storage=storage.clone() read_only=read_only beacon=beacon.clone() /> },
                             AppRoute::Start => html! { <Start /> },
                             AppRoute::Home => html! { <Home /> },
                         }
@@ -225,6 +287,10 @@ impl Component for App {
             </>
         }
     }
+
+    fn destroy(&mut self) {
+        self.watch_token.cancel();
+    }
 }
 
 impl App {
@@ -237,16 +303,36 @@ impl App {
         });
     }
 
+    /// Callback when IPFS switch to or from public gateways.
+    fn on_read_only(&mut self, read_only: bool) -> bool {
+        if read_only == self.read_only {
+            return false;
+        }
+
+        self.read_only = read_only;
+
+        // The daemon is back.
+        if !read_only && self.peer_id.is_none() {
+            self.check_ipfs();
+        }
+
+        true
+    }
+
     fn on_peer_id(&mut self, response: Result<PeerId>) -> bool {
         let id = match response {
             Ok(id) => id,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
-                return false;
+                self.ipfs_error = Some(e);
+                self.read_only = self.props.ipfs.is_read_only();
+                return true;
             }
         };
 
         self.peer_id = Some(id);
+        self.ipfs_error = None;
+        self.read_only = false;
 
         true
     }
@@ -283,7 +369,7 @@ impl App {
     }
 
     /// Callback when Ethereum Name Service resolve any name.
-    fn on_name(&mut self, res: (String, Result<Cid>)) -> bool {
+    fn on_name(&mut self, res: (String, Web3Result<Cid>)) -> bool {
         let (name, beacon_cid) = match res {
             (name, Ok(cid)) => (name, cid),
             (_, Err(e)) => {
@@ -292,8 +378,18 @@ impl App {
             }
         };
 
+        let mut render = false;
+
+        if self.ens_names.get(&beacon_cid) != Some(&name) {
+            Rc::make_mut(&mut self.ens_names).insert(beacon_cid, name.clone());
+
+            render = true;
+        }
+
+        render |= self.update_friend_names();
+
         if self.beacon_set.contains(&beacon_cid) {
-            return false;
+            return render;
         }
 
         spawn_local({
@@ -313,7 +409,35 @@ impl App {
         #[cfg(debug_assertions)]
         ConsoleService::info("App ENS Name Resolved");
 
-        false
+        render
+    }
+
+    /// Find the display names of your friends' beacons.
+    fn update_friend_names(&mut self) -> bool {
+        let mut names = HashSet::with_capacity(self.friends.friends.len());
+
+        for friend in self.friends.friends.iter() {
+            let beacon_cid = match &friend.friend {
+                Either::Right(ipld) => Some(ipld.link),
+                Either::Left(ens) => self
+                    .ens_names
+                    .iter()
+                    .find(|(_, name)| *name == ens)
+                    .map(|(cid, _)| *cid),
+            };
+
+            if let Some(name) = beacon_cid.and_then(|cid| self.display_names.get(&cid)) {
+                names.insert(name.clone());
+            }
+        }
+
+        if names == *self.friend_names {
+            return false;
+        }
+
+        self.friend_names = Rc::from(names);
+
+        true
     }
 
     fn resolve_content<T>(
@@ -348,6 +472,98 @@ impl App {
         }
     }
 
+    /// Callback sending the new node of this IPNS link through the usual callback.
+    fn update_callback<T>(
+        &self,
+        beacon_cid: Cid,
+        ipns: Cid,
+        callback: &Callback<CallbackResult<T>>,
+    ) -> Callback<Cid>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        let cb = callback.clone();
+        let ipfs = self.props.ipfs.clone();
+
+        Callback::from(move |cid: Cid| {
+            spawn_local({
+                let cb = cb.clone();
+                let ipfs = ipfs.clone();
+
+                async move {
+                    match ipfs.dag_get(cid, Option::<&str>::None).await {
+                        Ok(node) => cb.emit((beacon_cid, ipns, Ok((cid, node)))),
+                        Err(e) => cb.emit((beacon_cid, ipns, Err(e))),
+                    }
+                }
+            });
+        })
+    }
+
+    /// Keep resolving the feed and comments of every beacon, and the live, bans and mods of yours.
+    ///
+    /// The poll loop restarts with all the links, starting from the CIDs last seen.
+    fn watch_beacon(&mut self, beacon_cid: Cid, beacon: &Beacon, yours: bool) {
+        let mut links = Vec::with_capacity(5);
+
+        if let Some(ipns) = beacon.content_feed {
+            links.push((ipns, self.update_callback(beacon_cid, ipns, &self.feed_cb)));
+        }
+
+        if let Some(ipns) = beacon.comments {
+            links.push((
+                ipns,
+                self.update_callback(beacon_cid, ipns, &self.comments_cb),
+            ));
+        }
+
+        if yours {
+            if let Some(ipns) = beacon.live {
+                links.push((ipns, self.update_callback(beacon_cid, ipns, &self.live_cb)));
+            }
+
+            if let Some(ipns) = beacon.bans {
+                links.push((ipns, self.update_callback(beacon_cid, ipns, &self.bans_cb)));
+            }
+
+            if let Some(ipns) = beacon.mods {
+                links.push((ipns, self.update_callback(beacon_cid, ipns, &self.mods_cb)));
+            }
+        }
+
+        let count = self.watched.len();
+
+        for (ipns, cb) in links {
+            if !self.watched.iter().any(|(probe, _)| *probe == ipns) {
+                self.watched.push((ipns, cb));
+            }
+        }
+
+        if count == self.watched.len() {
+            return;
+        }
+
+        self.watch_token.cancel();
+        self.watch_token = CancelToken::new();
+
+        // Nodes already resolved are stored, they are not fetched again on the first poll.
+        let links = self
+            .watched
+            .iter()
+            .map(|(ipns, cb)| {
+                let last = self.props.storage.get_cid(&ipns.to_string());
+
+                (*ipns, last, cb.clone())
+            })
+            .collect();
+
+        spawn_local({
+            let ipfs = self.props.ipfs.with_token(&self.watch_token);
+
+            async move { ipfs.name_watch(links, IPNS_POLL_INTERVAL).await }
+        });
+    }
+
     /// Callback when IPFS dag get return any beacon.
     fn on_beacon(&mut self, response: (Cid, Result<Beacon>)) -> bool {
         let (beacon_cid, beacon) = match response {
@@ -367,6 +583,8 @@ impl App {
         self.resolve_content(beacon_cid, beacon.comments, &self.comments_cb);
 
         if self.beacon.is_some() {
+            self.watch_beacon(beacon_cid, &beacon, false);
+
             //Prevent resolving live, bans, mods of your friend's beacon.
             return false;
         }
@@ -376,7 +594,10 @@ impl App {
         self.resolve_content(beacon_cid, beacon.bans, &self.bans_cb);
         self.resolve_content(beacon_cid, beacon.mods, &self.mods_cb);
 
+        self.watch_beacon(beacon_cid, &beacon, true);
+
         if self.beacon.is_none() {
+            self.beacon_cid = Some(beacon_cid);
             self.beacon = Rc::from(beacon).into();
         }
 
@@ -397,6 +618,10 @@ impl App {
             return false;
         }
 
+        self.display_names
+            .insert(beacon_cid, identity.display_name.clone());
+        self.update_friend_names();
+
         Rc::make_mut(&mut self.content).insert_identity(beacon_cid, identity);
 
         self.props.storage.set_cid(&ipns.to_string(), &identity_cid);
@@ -439,6 +664,20 @@ impl App {
             return false;
         }
 
+        let commenters = Rc::make_mut(&mut self.commenters);
+
+        // Anyone can list a comment, the author is unknown when many beacons do.
+        for ipld in comments.comments.values().flatten() {
+            commenters
+                .entry(ipld.link)
+                .and_modify(|commenter| {
+                    if *commenter != Some(beacon_cid) {
+                        *commenter = None;
+                    }
+                })
+                .or_insert(Some(beacon_cid));
+        }
+
         Rc::make_mut(&mut self.content).insert_comments(beacon_cid, comments);
 
         self.props.storage.set_cid(&ipns.to_string(), &comments_cid);
@@ -483,6 +722,7 @@ impl App {
 
         self.friends_cid = friends_cid.into();
         self.friends = Rc::from(friends);
+        self.update_friend_names();
 
         #[cfg(debug_assertions)]
         ConsoleService::info("App Friends List Updated");
@@ -506,12 +746,76 @@ impl App {
         self.live_cid = live_cid.into();
         self.live = Rc::from(live);
 
+        // Chat limits are optional fields of the same node.
+        spawn_local({
+            let cb = self.limits_cb.clone();
+            let ipfs = self.props.ipfs.clone();
+
+            async move { cb.emit(ipfs.dag_get(live_cid, Option::<&str>::None).await) }
+        });
+
+        // The emote pack is optional too.
+        spawn_local({
+            let cb = self.emotes_cb.clone();
+            let ipfs = self.props.ipfs.clone();
+
+            async move { cb.emit(ipfs.dag_get(live_cid, Some(EMOTE_PACK_PATH)).await) }
+        });
+
         #[cfg(debug_assertions)]
         ConsoleService::info("App Live Data Updated");
 
         true
     }
 
+    /// Callback when IPFS dag get return the chat limits of your live node.
+    fn on_chat_limits(&mut self, res: Result<ChatLimits>) -> bool {
+        let limits = match res {
+            Ok(limits) => limits.clamped(),
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return false;
+            }
+        };
+
+        if limits == *self.limits {
+            return false;
+        }
+
+        self.limits = Rc::from(limits);
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info("App Chat Limits Updated");
+
+        true
+    }
+
+    /// Callback when IPFS dag get return the emote pack of your live node.
+    fn on_emotes(&mut self, res: Result<EmotePack>) -> bool {
+        let pack = match res {
+            Ok(pack) => pack,
+            Err(IpfsError::Aborted) => return false,
+            Err(_e) => {
+                // No pack on this live node.
+                #[cfg(debug_assertions)]
+                ConsoleService::info(&format!("{:?}", _e));
+
+                if self.emotes.is_empty() {
+                    return false;
+                }
+
+                EmotePack::default()
+            }
+        };
+
+        self.emotes = Rc::from(pack);
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info("App Emote Pack Updated");
+
+        true
+    }
+
     /// Callback when IPFS dag get return your ban list.
     fn on_ban_list(&mut self, res: CallbackResult<Bans>) -> bool {
         let (_, ipns, bans_cid, bans) = match on_node(res) {
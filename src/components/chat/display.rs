@@ -1,53 +1,148 @@
-use std::{collections::VecDeque, rc::Rc, str};
+use std::{
+    collections::{HashSet, VecDeque},
+    rc::Rc,
+    str,
+};
 
 use crate::{
     components::{
-        chat::message::{MessageData, UIMessage},
+        chat::{
+            emotes::EmotePack,
+            inputs::SIGN_MSG_KEY,
+            limits::{ChatLimits, FloodGuard, Verdict},
+            message::{MessageData, UIMessage},
+            modes::{is_emote_only, ChatModes, Payload},
+        },
         IPFSPubSubError,
     },
-    utils::IpfsService,
+    utils::{CancelToken, IpfsError, IpfsService, LocalStorage, PubsubState, Web3Service},
 };
 
 use futures_util::future::AbortHandle;
 
 use wasm_bindgen_futures::spawn_local;
 
-use web_sys::Element;
+use web_sys::{Element, Notification, NotificationOptions, NotificationPermission};
 
 use yew::{
-    prelude::{html, Component, ComponentLink, Html, Properties, ShouldRender},
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
     services::ConsoleService,
-    Callback,
+    Callback, Event, MouseEvent,
 };
 
 use linked_data::{
     chat::{ChatId, ChatMessage, MessageType},
+    friends::Friendlies,
     live::Live,
-    moderation::{Ban, Bans, ChatModerationCache, Moderators},
+    moderation::{Ban, Bans, ChatModerationCache, Moderator, Moderators},
     signature::SignedMessage,
     PeerId,
 };
 
 use blockies::Ethereum;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use either::Either;
+
+use web3::types::Address;
+
+use cid::Cid;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+type Web3Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Number of messages kept when not configured.
+pub const DEFAULT_CHAT_HISTORY: usize = 500;
+
+/// Estimated message height in pixels, used to size the space of messages not rendered.
+const ROW_HEIGHT: i32 = 80;
+
+/// Number of messages rendered above and below the visible ones.
+const OVERSCAN: usize = 5;
+
+/// Same as the moderation cache capacity.
+const KNOWN_PEERS: usize = 100;
+
+/// A verified chat participant.
+#[derive(Clone, PartialEq)]
+pub struct ChatSender {
+    pub peer: PeerId,
+    pub address: [u8; 20],
+    pub name: Rc<str>,
+}
+
+/// Chat activity reported to the moderation panel.
+pub enum ChatEvent {
+    /// First verified message from this sender.
+    Sender(ChatSender),
+
+    /// Banned this session by the streamer or a moderator.
+    Ban(ChatSender),
+
+    /// New chat modes from the streamer or a moderator.
+    Modes(ChatModes),
+
+    /// You became or stopped being a moderator.
+    Authorised(bool),
+
+    /// Names known by the moderation cache, most recent first.
+    Names(Rc<Vec<Rc<str>>>),
+}
 
 pub struct Display {
     props: Props,
 
-    error: bool,
+    error: Option<IpfsError>,
+    reconnecting: Option<u64>,
 
-    msg_cb: Callback<(PeerId, ChatMessage, Result<SignedMessage<ChatId>>)>,
+    msg_cb: Callback<(PeerId, Payload, Result<SignedMessage<ChatId>>)>,
 
     pubsub_cb: Callback<Result<(PeerId, Vec<u8>)>>,
+    state_cb: Callback<PubsubState>,
     handle: AbortHandle,
+    token: CancelToken,
 
     img_gen: Ethereum,
 
     mod_db: ChatModerationCache,
+    guard: FloodGuard,
+    modes: ChatModes,
+    authorised: bool,
+
+    /// Your chat name, messages mentioning it are highlighted.
+    my_name: Option<String>,
+    /// Desktop notification on mentions when the tab is not focused.
+    notify: bool,
+    /// Peers added to the moderation cache, most recent first.
+    known: VecDeque<PeerId>,
+
+    /// Addresses of the streamer's friends.
+    friend_addrs: HashSet<[u8; 20]>,
+    friend_cb: Callback<Web3Result<Address>>,
+    /// Addresses removed from the ban list this session.
+    lifted_addrs: HashSet<[u8; 20]>,
+    /// Senders whose ENS name was checked against friends added by beacon CID.
+    checked_addrs: HashSet<[u8; 20]>,
+    beacon_cb: Callback<([u8; 20], Web3Result<Cid>)>,
 
     chat_element: Option<Element>,
-
+    scroll_cb: Callback<Event>,
+    jump_cb: Callback<MouseEvent>,
+
+    /// Index of the first message rendered when not following.
+    first: usize,
+    /// Number of messages rendered.
+    visible: usize,
+    /// Scroll to new messages, paused when scrolled up.
+    follow: bool,
+    /// Messages received while paused.
+    unread: usize,
+    /// Messages dropped above the rendered ones since last render.
+    shift: usize,
+
+    ban_cb: Callback<usize>,
+    mod_cb: Callback<usize>,
+
+    history: usize,
     next_id: usize,
     chat_messages: VecDeque<MessageData>,
 }
@@ -55,15 +150,35 @@ pub struct Display {
 #[allow(clippy::large_enum_variant)]
 pub enum Msg {
     PubSub(Result<(PeerId, Vec<u8>)>),
-    Origin((PeerId, ChatMessage, Result<SignedMessage<ChatId>>)),
+    PubSubState(PubsubState),
+    Origin((PeerId, Payload, Result<SignedMessage<ChatId>>)),
+    Friend(Web3Result<Address>),
+    FriendBeacon(([u8; 20], Web3Result<Cid>)),
+    MyName(Result<SignedMessage<ChatId>>),
+    Ban(usize),
+    Mod(usize),
+    Scroll,
+    Jump,
 }
 
 #[derive(Properties, Clone)]
 pub struct Props {
     pub ipfs: IpfsService,
+    pub web3: Web3Service,
+    pub storage: LocalStorage,
+
+    /// Local node, used to know if you are a moderator.
+    pub peer_id: PeerId,
     pub live: Rc<Live>,
+    pub limits: Rc<ChatLimits>,
+    pub emotes: Rc<EmotePack>,
     pub mods: Rc<Moderators>,
     pub bans: Rc<Bans>,
+    pub friends: Rc<Friendlies>,
+
+    /// Peers unbanned this session.
+    pub unbanned: Rc<HashSet<PeerId>>,
+    pub event_cb: Callback<ChatEvent>,
 }
 
 impl Component for Display {
@@ -82,6 +197,7 @@ impl Component for Display {
         };
 
         let pubsub_cb = link.callback(Msg::PubSub);
+        let state_cb = link.callback(Msg::PubSubState);
         let (handle, regis) = AbortHandle::new_pair();
 
         if !props.live.chat_topic.is_empty() {
@@ -89,39 +205,102 @@ impl Component for Display {
                 let ipfs = props.ipfs.clone();
                 let sub_topic = props.live.chat_topic.clone();
                 let cb = pubsub_cb.clone();
+                let state_cb = state_cb.clone();
 
-                async move { ipfs.pubsub_sub(sub_topic, cb, regis).await }
+                async move { ipfs.pubsub_sub(sub_topic, cb, state_cb, regis).await }
             });
         }
 
+        let history = props
+            .storage
+            .get_chat_history()
+            .unwrap_or(DEFAULT_CHAT_HISTORY);
+
+        let limits = *props.limits;
+        let notify = props.storage.get_chat_notify();
+
         #[cfg(debug_assertions)]
         ConsoleService::info("Chat Display Created");
 
-        Self {
+        let mut comp = Self {
             props,
 
-            error: false,
+            error: None,
+            reconnecting: None,
 
             msg_cb: link.callback(Msg::Origin),
 
             pubsub_cb,
+            state_cb,
             handle,
+            token: CancelToken::new(),
 
             img_gen,
 
             mod_db: ChatModerationCache::new(100, 100),
+            guard: FloodGuard::new(limits),
+            modes: ChatModes::default(),
+            authorised: false,
+
+            my_name: None,
+            notify,
+            known: VecDeque::with_capacity(KNOWN_PEERS),
+
+            friend_addrs: HashSet::default(),
+            friend_cb: link.callback(Msg::Friend),
+            checked_addrs: HashSet::default(),
+            lifted_addrs: HashSet::default(),
+            beacon_cb: link.callback(Msg::FriendBeacon),
 
             chat_element: None,
+            scroll_cb: link.callback(|_event: Event| Msg::Scroll),
+            jump_cb: link.callback(|_event: MouseEvent| Msg::Jump),
+
+            first: 0,
+            visible: 20,
+            follow: true,
+            unread: 0,
+            shift: 0,
 
-            chat_messages: VecDeque::with_capacity(20),
+            ban_cb: link.callback(Msg::Ban),
+            mod_cb: link.callback(Msg::Mod),
+
+            history,
+            chat_messages: VecDeque::with_capacity(history.min(100)),
             next_id: 0,
-        }
+        };
+
+        comp.resolve_friends();
+        comp.check_authorised();
+        comp.get_my_name(&link);
+
+        comp
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::PubSub(result) => self.on_pubsub_update(result),
+            Msg::PubSubState(state) => self.on_pubsub_state(state),
             Msg::Origin((peer, msg, result)) => self.on_signed_msg(peer, msg, result),
+            Msg::Friend(result) => self.on_friend_address(result),
+            Msg::FriendBeacon((address, result)) => self.on_friend_beacon(address, result),
+            Msg::MyName(result) => {
+                match result {
+                    Ok(signed_msg) => self.my_name = Some(signed_msg.data.name),
+                    Err(e) => ConsoleService::error(&format!("{:?}", e)),
+                }
+
+                false
+            }
+            Msg::Ban(id) => self.ban(id),
+            Msg::Mod(id) => self.promote(id),
+            Msg::Scroll => self.on_scroll(),
+            Msg::Jump => {
+                self.follow = true;
+                self.unread = 0;
+
+                true
+            }
         }
     }
 
@@ -129,7 +308,11 @@ impl Component for Display {
         if !Rc::ptr_eq(&self.props.live, &props.live) {
             self.handle.abort();
 
+            self.guard.set_limits(*props.limits);
+
             self.props = props;
+            self.error = None;
+            self.reconnecting = None;
 
             if !self.props.live.chat_topic.is_empty() {
                 let (handle, regis) = AbortHandle::new_pair();
@@ -140,40 +323,118 @@ impl Component for Display {
                     let ipfs = self.props.ipfs.clone();
                     let sub_topic = self.props.live.chat_topic.clone();
                     let cb = self.pubsub_cb.clone();
+                    let state_cb = self.state_cb.clone();
 
-                    async move { ipfs.pubsub_sub(sub_topic, cb, regis).await }
+                    async move { ipfs.pubsub_sub(sub_topic, cb, state_cb, regis).await }
                 });
             }
 
             #[cfg(debug_assertions)]
             ConsoleService::info("Chat Display Changed");
+
+            return false;
         }
 
-        false
+        // Bans applied this session are lifted for addresses removed from the list.
+        for address in self
+            .props
+            .bans
+            .banned_addrs
+            .difference(&props.bans.banned_addrs)
+        {
+            self.lifted_addrs.insert(*address);
+        }
+
+        for address in props.bans.banned_addrs.iter() {
+            self.lifted_addrs.remove(address);
+        }
+
+        // Updated lists apply to new messages.
+        self.props.bans = props.bans;
+        self.props.unbanned = props.unbanned;
+
+        if !Rc::ptr_eq(&self.props.limits, &props.limits) {
+            self.guard.set_limits(*props.limits);
+            self.props.limits = props.limits;
+        }
+
+        if !Rc::ptr_eq(&self.props.friends, &props.friends) {
+            self.props.friends = props.friends;
+            self.friend_addrs.clear();
+            self.checked_addrs.clear();
+            self.resolve_friends();
+        }
+
+        let mut render = false;
+
+        if !Rc::ptr_eq(&self.props.emotes, &props.emotes) {
+            self.props.emotes = props.emotes;
+
+            render = true;
+        }
+
+        if !Rc::ptr_eq(&self.props.mods, &props.mods) {
+            self.props.mods = props.mods;
+            self.check_authorised();
+
+            render = true;
+        }
+
+        render
     }
 
     fn view(&self) -> Html {
-        if self.error {
-            return html! { <IPFSPubSubError /> };
+        if let Some(error) = &self.error {
+            return html! { <IPFSPubSubError error=Some(error.clone()) /> };
         }
 
         html! {
-            <div id="chat_display" class="box" style="overflow-y: scroll;height: 60vh;scroll-behavior: smooth;" >
+            <>
             {
-                for self.chat_messages.iter().map(|cm| html! {
-                    <UIMessage key=cm.id.to_string() message_data=cm.clone() />
-                })
+                match self.reconnecting {
+                    Some(seconds) => html! {
+                        <p class="help is-warning"> { format!("Chat disconnected, reconnecting in {}s...", seconds) } </p>
+                    },
+                    None => html! {},
+                }
             }
-            </div>
+            {
+                if self.modes.friends_only && self.friend_addrs.is_empty() {
+                    html! {
+                        <p class="help is-warning"> { "Friends only mode is on but no friend's address is known, messages are hidden." } </p>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            { self.render_messages() }
+            {
+                if self.unread > 0 {
+                    html! {
+                        <ybc::Button classes=classes!("is-info", "is-small", "is-fullwidth") onclick=self.jump_cb.clone() >
+                            { format!("{} new messages", self.unread) }
+                        </ybc::Button>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            </>
         }
     }
 
     fn rendered(&mut self, first_render: bool) {
         if !first_render {
             if let Some(element) = self.chat_element.as_mut() {
-                element.set_scroll_top(element.scroll_height());
+                if self.follow {
+                    element.set_scroll_top(element.scroll_height());
+                } else if self.shift > 0 {
+                    element.set_scroll_top(element.scroll_top() - self.shift as i32 * ROW_HEIGHT);
+                }
             }
 
+            self.shift = 0;
+
             return;
         }
 
@@ -208,19 +469,82 @@ impl Component for Display {
     }
 
     fn destroy(&mut self) {
-        self.handle.abort()
+        self.handle.abort();
+        self.token.cancel();
     }
 }
 
 impl Display {
+    /// Only messages around the visible area are rendered, the rest is empty space.
+    fn render_messages(&self) -> Html {
+        let (first, end) = self.rendered_range();
+        let len = self.chat_messages.len();
+
+        let top = first as i32 * ROW_HEIGHT;
+        let bottom = (len - end) as i32 * ROW_HEIGHT;
+
+        html! {
+            <div id="chat_display" class="box" style="overflow-y: scroll;height: 60vh;" onscroll=self.scroll_cb.clone() >
+                <div style=format!("height: {}px;", top) ></div>
+                {
+                    for self.chat_messages.range(first..end).map(|cm| html! {
+                        <UIMessage key=cm.id.to_string() message_data=cm.clone() ipfs=self.props.ipfs.clone() emotes=self.props.emotes.clone() moderator=self.can_moderate(cm) promote=self.is_listed(&self.props.peer_id) ban_cb=self.ban_cb.clone() mod_cb=self.mod_cb.clone() />
+                    })
+                }
+                <div style=format!("height: {}px;", bottom) ></div>
+            </div>
+        }
+    }
+
+    fn rendered_range(&self) -> (usize, usize) {
+        let len = self.chat_messages.len();
+
+        if self.follow {
+            return (len.saturating_sub(self.visible), len);
+        }
+
+        let first = self.first.min(len);
+        let end = (first + self.visible).min(len);
+
+        (first, end)
+    }
+
+    /// Callback when the chat is scrolled, by the user or automatically.
+    fn on_scroll(&mut self) -> bool {
+        let element = match self.chat_element.as_ref() {
+            Some(element) => element,
+            None => return false,
+        };
+
+        let top = element.scroll_top();
+        let height = element.client_height();
+
+        let follow = top + height >= element.scroll_height() - ROW_HEIGHT / 2;
+        let visible = (height / ROW_HEIGHT).max(0) as usize + 1 + 2 * OVERSCAN;
+        let first = ((top / ROW_HEIGHT).max(0) as usize).saturating_sub(OVERSCAN);
+
+        if follow == self.follow && visible == self.visible && (follow || first == self.first) {
+            return false;
+        }
+
+        if follow {
+            self.unread = 0;
+        }
+
+        self.follow = follow;
+        self.visible = visible;
+        self.first = first;
+
+        true
+    }
+
     /// Callback when GossipSub receive a message.
     fn on_pubsub_update(&mut self, result: Result<(PeerId, Vec<u8>)>) -> bool {
         let res = match result {
             Ok(res) => res,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
-                self.error = true;
-                return true;
+                return false;
             }
         };
 
@@ -232,11 +556,15 @@ impl Display {
         #[cfg(debug_assertions)]
         ConsoleService::info(&format!("Sender => {:?}", peer_id));
 
-        if self.mod_db.is_banned(&peer_id) {
+        if self.is_banned(&peer_id) {
+            return false;
+        }
+
+        if self.guard.is_muted(&peer_id) {
             return false;
         }
 
-        let msg: ChatMessage = match serde_json::from_slice(&data) {
+        let msg: Payload = match serde_json::from_slice(&data) {
             Ok(msg) => msg,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
@@ -244,7 +572,7 @@ impl Display {
             }
         };
 
-        if !self.mod_db.is_verified(&peer_id, &msg.signature.link) {
+        if !self.mod_db.is_verified(&peer_id, &msg.signature().link) {
             self.get_origin(peer_id, msg);
             return false;
         }
@@ -252,11 +580,26 @@ impl Display {
         self.process_msg(peer_id, msg)
     }
 
-    fn get_origin(&self, peer_id: PeerId, msg: ChatMessage) {
+    /// Callback when the subscription connects, drops or fails.
+    fn on_pubsub_state(&mut self, state: PubsubState) -> bool {
+        match state {
+            PubsubState::Connected => self.reconnecting = None,
+            PubsubState::Reconnecting(seconds) => self.reconnecting = Some(seconds),
+            PubsubState::Failed(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                self.reconnecting = None;
+                self.error = Some(e);
+            }
+        }
+
+        true
+    }
+
+    fn get_origin(&self, peer_id: PeerId, msg: Payload) {
         spawn_local({
             let cb = self.msg_cb.clone();
-            let ipfs = self.props.ipfs.clone();
-            let cid = msg.signature.link;
+            let ipfs = self.props.ipfs.with_token(&self.token);
+            let cid = msg.signature().link;
 
             async move {
                 cb.emit((
@@ -272,11 +615,12 @@ impl Display {
     fn on_signed_msg(
         &mut self,
         peer: PeerId,
-        msg: ChatMessage,
+        msg: Payload,
         response: Result<SignedMessage<ChatId>>,
     ) -> bool {
         let sign_msg = match response {
             Ok(m) => m,
+            Err(IpfsError::Aborted) => return false,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
                 return false;
@@ -288,13 +632,17 @@ impl Display {
 
         let trusted = sign_msg.verify();
 
+        let name = Rc::from(sign_msg.data.name.as_str());
+
         self.mod_db.add_peer(
             sign_msg.data.peer_id,
-            msg.signature.link,
+            msg.signature().link,
             sign_msg.address,
             Some(sign_msg.data.name),
         );
 
+        self.update_names(sign_msg.data.peer_id);
+
         if peer != sign_msg.data.peer_id {
             self.mod_db.ban_peer(&peer);
             return false;
@@ -312,19 +660,55 @@ impl Display {
         #[cfg(debug_assertions)]
         ConsoleService::info("Verifiable => true");
 
-        if self.props.bans.banned_addrs.contains(&sign_msg.address) {
+        if self.props.bans.banned_addrs.contains(&sign_msg.address)
+            && !self.props.unbanned.contains(&peer)
+        {
             self.mod_db.ban_peer(&peer);
             return false;
         }
 
+        self.props.event_cb.emit(ChatEvent::Sender(ChatSender {
+            peer,
+            address: sign_msg.address,
+            name,
+        }));
+
+        if peer == self.props.peer_id {
+            self.check_authorised();
+        }
+
+        self.check_friend(sign_msg.address);
+
         self.process_msg(peer, msg)
     }
 
-    fn process_msg(&mut self, peer: PeerId, msg: ChatMessage) -> bool {
-        match msg.message {
-            MessageType::Text(text) => self.update_display(&peer, &text),
-            MessageType::Ban(ban) => self.update_bans(&peer, &ban),
-            MessageType::Mod(_) => false, //TODO
+    /// Report names of the moderation cache for mention autocomplete.
+    fn update_names(&mut self, peer: PeerId) {
+        self.known.retain(|probe| *probe != peer);
+        self.known.push_front(peer);
+        self.known.truncate(KNOWN_PEERS);
+
+        let mut names: Vec<Rc<str>> = Vec::with_capacity(self.known.len());
+
+        for peer in self.known.iter() {
+            if let Some(name) = self.mod_db.get_name(peer) {
+                if !names.iter().any(|probe| **probe == *name) {
+                    names.push(Rc::from(name));
+                }
+            }
+        }
+
+        self.props.event_cb.emit(ChatEvent::Names(Rc::from(names)));
+    }
+
+    fn process_msg(&mut self, peer: PeerId, msg: Payload) -> bool {
+        match msg {
+            Payload::Chat(msg) => match msg.message {
+                MessageType::Text(text) => self.update_display(&peer, &text),
+                MessageType::Ban(ban) => self.update_bans(&peer, &ban),
+                MessageType::Mod(moderator) => self.update_mods(&peer, &moderator),
+            },
+            Payload::Modes(msg) => self.update_modes(&peer, msg.modes),
         }
     }
 
@@ -332,6 +716,25 @@ impl Display {
         #[cfg(debug_assertions)]
         ConsoleService::info(&format!("Message => {}", text));
 
+        // The streamer and moderators are trusted not to flood.
+        if !self.is_authorised(peer) {
+            if !self.is_allowed(peer, text) {
+                #[cfg(debug_assertions)]
+                ConsoleService::warn(&format!("Message Dropped => {:?}", self.modes));
+
+                return false;
+            }
+
+            let verdict = self.guard.check(*peer, text);
+
+            if verdict != Verdict::Accepted {
+                #[cfg(debug_assertions)]
+                ConsoleService::warn(&format!("Message Dropped => {:?}", verdict));
+
+                return false;
+            }
+        }
+
         let address = match self.mod_db.get_address(peer) {
             Some(addrs) => addrs,
             None => {
@@ -350,18 +753,34 @@ impl Display {
             }
         };
 
+        let mention = *peer != self.props.peer_id && self.is_mention(text);
+
+        if mention {
+            self.notify_mention(name, text);
+        }
+
         let mut data = Vec::new();
 
         if let Err(e) = self.img_gen.create_icon(&mut data, address) {
             ConsoleService::error(&format!("{:?}", e));
         }
 
-        let msg_data = MessageData::new(self.next_id, &data, name, text);
+        let msg_data = MessageData::new(self.next_id, *peer, *address, &data, name, text, mention);
 
         self.chat_messages.push_back(msg_data);
 
-        if self.chat_messages.len() >= 10 {
+        if self.chat_messages.len() > self.history {
             self.chat_messages.pop_front();
+
+            // Keep the same messages in view while paused.
+            if !self.follow && self.first > 0 {
+                self.first -= 1;
+                self.shift += 1;
+            }
+        }
+
+        if !self.follow {
+            self.unread += 1;
         }
 
         self.next_id += 1;
@@ -369,22 +788,358 @@ impl Display {
         true
     }
 
+    /// Your chat name is recovered from the signed message of previous sessions.
+    fn get_my_name(&self, link: &ComponentLink<Self>) {
+        let cid = match self.props.storage.get_cid(SIGN_MSG_KEY) {
+            Some(cid) => cid,
+            None => return,
+        };
+
+        spawn_local({
+            let cb = link.callback_once(Msg::MyName);
+            let ipfs = self.props.ipfs.with_token(&self.token);
+
+            async move { cb.emit(ipfs.dag_get(cid, Option::<String>::None).await) }
+        });
+    }
+
+    /// True if the text contains @ followed by your name.
+    fn is_mention(&self, text: &str) -> bool {
+        let name = match self
+            .my_name
+            .as_deref()
+            .or_else(|| self.mod_db.get_name(&self.props.peer_id))
+        {
+            Some(name) if !name.is_empty() => name.to_lowercase(),
+            _ => return false,
+        };
+
+        let text = text.to_lowercase();
+
+        text.match_indices('@').any(|(index, _)| {
+            let rest = &text[index + 1..];
+
+            rest.starts_with(&name)
+                && !rest[name.len()..]
+                    .chars()
+                    .next()
+                    .map_or(false, |c| c.is_alphanumeric() || c == '_')
+        })
+    }
+
+    fn notify_mention(&self, name: &str, text: &str) {
+        if !self.notify || Notification::permission() != NotificationPermission::Granted {
+            return;
+        }
+
+        let focused = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.has_focus().ok())
+            .unwrap_or(true);
+
+        if focused {
+            return;
+        }
+
+        let mut options = NotificationOptions::new();
+        options.body(text);
+
+        if let Err(e) = Notification::new_with_options(&format!("{} mentioned you", name), &options)
+        {
+            ConsoleService::error(&format!("{:?}", e));
+        }
+    }
+
     fn update_bans(&mut self, peer: &PeerId, ban: &Ban) -> bool {
-        let address = match self.mod_db.get_address(peer) {
-            Some(addrs) => addrs,
-            None => {
-                #[cfg(debug_assertions)]
-                ConsoleService::error("No Address");
-                return false;
+        if !self.is_authorised(peer) {
+            return false;
+        }
+
+        // The streamer and moderators cannot be banned.
+        if self.is_authorised(&ban.ban_peer)
+            || self.props.mods.moderator_addrs.contains(&ban.ban_addrs)
+        {
+            return false;
+        }
+
+        self.mod_db.ban_peer(&ban.ban_peer);
+        self.lifted_addrs.remove(&ban.ban_addrs);
+
+        let name = self.mod_db.get_name(&ban.ban_peer).unwrap_or_default();
+
+        self.props.event_cb.emit(ChatEvent::Ban(ChatSender {
+            peer: ban.ban_peer,
+            address: ban.ban_addrs,
+            name: Rc::from(name),
+        }));
+
+        let count = self.chat_messages.len();
+
+        self.chat_messages.retain(|data| data.peer != ban.ban_peer);
+
+        count != self.chat_messages.len()
+    }
+
+    fn update_mods(&mut self, peer: &PeerId, moderator: &Moderator) -> bool {
+        if !self.is_listed(peer) {
+            return false;
+        }
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("Moderator => {:?}", moderator.mod_peer));
+
+        self.mod_db.mod_peer(&moderator.mod_peer);
+
+        if moderator.mod_peer == self.props.peer_id {
+            self.check_authorised();
+        }
+
+        // Controls may appear or disappear.
+        true
+    }
+
+    fn update_modes(&mut self, peer: &PeerId, modes: ChatModes) -> bool {
+        if !self.is_authorised(peer) {
+            return false;
+        }
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("Chat Modes => {:?}", modes));
+
+        // The friends only notice may change.
+        let render = self.modes.friends_only != modes.friends_only;
+
+        self.modes = modes;
+        self.guard.set_slow(modes.slow);
+
+        self.props.event_cb.emit(ChatEvent::Modes(modes));
+
+        render
+    }
+
+    /// Check the message against the current chat modes.
+    fn is_allowed(&self, peer: &PeerId, text: &str) -> bool {
+        if self.modes.emote_only && !is_emote_only(text, &self.props.emotes) {
+            return false;
+        }
+
+        if self.modes.friends_only {
+            return match self.mod_db.get_address(peer) {
+                Some(address) => self.friend_addrs.contains(address),
+                None => false,
+            };
+        }
+
+        true
+    }
+
+    /// Let the page know when you become or stop being a moderator.
+    fn check_authorised(&mut self) {
+        let authorised = self.is_authorised(&self.props.peer_id);
+
+        if authorised != self.authorised {
+            self.authorised = authorised;
+
+            self.props.event_cb.emit(ChatEvent::Authorised(authorised));
+        }
+    }
+
+    /// Resolve the addresses of friends added by ENS name.
+    ///
+    /// Friends added by beacon CID are found later, see `check_friend`.
+    fn resolve_friends(&self) {
+        for friend in self.props.friends.friends.iter() {
+            let name = match &friend.friend {
+                Either::Left(name) => name.clone(),
+                Either::Right(_) => continue,
+            };
+
+            spawn_local({
+                let cb = self.friend_cb.clone();
+                let web3 = self.props.web3.clone();
+
+                async move { cb.emit(web3.name_address(&name).await) }
+            });
+        }
+    }
+
+    fn on_friend_address(&mut self, response: Web3Result<Address>) -> bool {
+        match response {
+            Ok(address) => self.add_friend(address.to_fixed_bytes()),
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                false
             }
+        }
+    }
+
+    /// Check once if this sender's ENS name points to the beacon of a friend added by CID.
+    ///
+    /// Messages sent before the check completes are not shown in friends only mode.
+    fn check_friend(&mut self, address: [u8; 20]) {
+        if self.friend_addrs.contains(&address) || !self.checked_addrs.insert(address) {
+            return;
+        }
+
+        if !self
+            .props
+            .friends
+            .friends
+            .iter()
+            .any(|friend| friend.friend.is_right())
+        {
+            return;
+        }
+
+        spawn_local({
+            let cb = self.beacon_cb.clone();
+            let web3 = self.props.web3.clone();
+
+            async move { cb.emit((address, web3.address_beacon(Address::from(address)).await)) }
+        });
+    }
+
+    fn on_friend_beacon(&mut self, address: [u8; 20], response: Web3Result<Cid>) -> bool {
+        let beacon_cid = match response {
+            Ok(cid) => cid,
+            Err(_) => return false,
         };
 
-        if !self.props.mods.moderator_addrs.contains(address) {
+        let is_friend = self
+            .props
+            .friends
+            .friends
+            .iter()
+            .any(|friend| match &friend.friend {
+                Either::Right(ipld) => ipld.link == beacon_cid,
+                Either::Left(_) => false,
+            });
+
+        if !is_friend {
             return false;
         }
 
-        self.mod_db.ban_peer(&ban.ban_peer);
+        self.add_friend(address)
+    }
+
+    /// Return true when the friends only notice must be hidden.
+    fn add_friend(&mut self, address: [u8; 20]) -> bool {
+        let first = self.friend_addrs.is_empty();
+
+        self.friend_addrs.insert(address) && first && self.modes.friends_only
+    }
+
+    /// Banned this session, unless unbanned or removed from the ban list since.
+    fn is_banned(&self, peer: &PeerId) -> bool {
+        if !self.mod_db.is_banned(peer) || self.props.unbanned.contains(peer) {
+            return false;
+        }
+
+        match self.mod_db.get_address(peer) {
+            Some(address) => !self.lifted_addrs.contains(address),
+            None => true,
+        }
+    }
+
+    /// The streamer, moderators from the list and moderators promoted this session.
+    fn is_authorised(&self, peer: &PeerId) -> bool {
+        self.mod_db.is_mod(peer) || self.is_listed(peer)
+    }
+
+    /// The streamer or a moderator of the beacon's list, who alone can promote.
+    fn is_listed(&self, peer: &PeerId) -> bool {
+        if *peer == self.props.live.peer_id {
+            return true;
+        }
+
+        match self.mod_db.get_address(peer) {
+            Some(address) => self.props.mods.moderator_addrs.contains(address),
+            None => false,
+        }
+    }
+
+    /// Moderators can act on anyone but themselves and other moderators.
+    fn can_moderate(&self, data: &MessageData) -> bool {
+        data.peer != self.props.peer_id
+            && self.is_authorised(&self.props.peer_id)
+            && !self.is_authorised(&data.peer)
+    }
+
+    fn ban(&mut self, id: usize) -> bool {
+        let data = match self.chat_messages.iter().find(|data| data.id == id) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        if !self.can_moderate(data) {
+            return false;
+        }
+
+        let ban = Ban {
+            ban_peer: data.peer,
+            ban_addrs: data.address,
+        };
+
+        self.publish(MessageType::Ban(ban));
+
+        false
+    }
+
+    fn promote(&mut self, id: usize) -> bool {
+        let data = match self.chat_messages.iter().find(|data| data.id == id) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        if !self.can_moderate(data) || !self.is_listed(&self.props.peer_id) {
+            return false;
+        }
+
+        let moderator = Moderator {
+            mod_peer: data.peer,
+            mod_addrs: data.address,
+        };
+
+        self.publish(MessageType::Mod(moderator));
 
         false
     }
+
+    /// Send moderation message via gossipsub.
+    ///
+    /// Your own messages are received too and processed like any other.
+    fn publish(&self, message: MessageType) {
+        let cid = match self.props.storage.get_cid(SIGN_MSG_KEY) {
+            Some(cid) => cid,
+            None => {
+                #[cfg(debug_assertions)]
+                ConsoleService::error("No Signed Message CID");
+                return;
+            }
+        };
+
+        let chat_msg = ChatMessage {
+            message,
+            signature: cid.into(),
+        };
+
+        let json_string = match serde_json::to_string(&chat_msg) {
+            Ok(json_string) => json_string,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return;
+            }
+        };
+
+        spawn_local({
+            let ipfs = self.props.ipfs.with_token(&self.token);
+            let topic = self.props.live.chat_topic.clone();
+
+            async move {
+                if let Err(e) = ipfs.pubsub_pub(topic, json_string).await {
+                    ConsoleService::error(&format!("{:#?}", e));
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use linked_data::IPLDLink;
+
+/// Path from the live node to the streamer's emote pack.
+pub const EMOTE_PACK_PATH: &str = "/emote_pack";
+
+/// Emote pack referenced by the live node.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EmotePack {
+    /// Short codes, without colons, mapped to MimeTyped images.
+    pub emotes: BTreeMap<String, IPLDLink>,
+}
+
+impl EmotePack {
+    pub fn is_empty(&self) -> bool {
+        self.emotes.is_empty()
+    }
+
+    /// Link to the image of this code, if part of the pack.
+    pub fn get(&self, code: &str) -> Option<&IPLDLink> {
+        self.emotes.get(code)
+    }
+}
+
+/// Code of a word like `:wave:`.
+pub fn emote_code(word: &str) -> Option<&str> {
+    let code = word.strip_prefix(':')?.strip_suffix(':')?;
+
+    if code.is_empty()
+        || !code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn codes_between_colons() {
+        assert_eq!(emote_code(":wave:"), Some("wave"));
+        assert_eq!(emote_code(":pog_champ-2:"), Some("pog_champ-2"));
+    }
+
+    #[wasm_bindgen_test]
+    fn malformed_codes_are_rejected() {
+        for word in &[
+            "::", ":", "wave", ":wave", "wave:", "::wave::", ":wa ve:", ":wave!:", ":été:", ":<b>:",
+        ] {
+            assert_eq!(emote_code(word), None, "{} was accepted", word);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn pack_lookup() {
+        let pack: EmotePack = serde_json::from_str(
+            r#"{ "emotes": { "wave": { "/": "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi" } } }"#,
+        )
+        .unwrap();
+
+        assert!(!pack.is_empty());
+        assert!(pack.get("wave").is_some());
+        assert!(pack.get(":wave:").is_none());
+    }
+}
@@ -1,6 +1,14 @@
-use std::{rc::Rc, str};
-
-use crate::utils::{IpfsService, LocalStorage, Web3Service};
+use std::{rc::Rc, str, time::Duration};
+
+use crate::{
+    components::chat::{
+        emotes::EmotePack,
+        limits::ChatLimits,
+        modes::{is_emote_only, ChatModes},
+    },
+    components::Image,
+    utils::{sleep, IpfsError, IpfsService, LocalStorage, Web3Service},
+};
 
 use wasm_bindgen_futures::spawn_local;
 
@@ -20,9 +28,10 @@ use linked_data::{
 
 use web3::types::Address;
 
-const SIGN_MSG_KEY: &str = "signed_message";
+pub const SIGN_MSG_KEY: &str = "signed_message";
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
+type Web3Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 enum DisplayState {
     /// Before anything, ask user to connect account.
@@ -40,6 +49,12 @@ pub struct Inputs {
     state: DisplayState,
 
     temp_msg: String,
+    too_long: bool,
+    emote_error: bool,
+    picker: bool,
+
+    /// Seconds before the next message in slow mode.
+    cooldown: u64,
 
     address: Option<Address>,
     peer_id: Option<PeerId>,
@@ -50,14 +65,18 @@ pub struct Inputs {
 
 pub enum Msg {
     Set(String),
+    Mention(Rc<str>),
+    Picker,
+    Emote(Rc<str>),
     Enter,
+    Tick,
     Connect,
     PeerID(Result<PeerId>),
-    Account(Result<Address>),
-    AccountName(Result<String>),
+    Account(Web3Result<Address>),
+    AccountName(Web3Result<String>),
     SetName(String),
     SubmitName,
-    Signed(Result<[u8; 65]>),
+    Signed(Web3Result<[u8; 65]>),
     Minted(Result<Cid>),
     Recover(Result<SignedMessage<ChatId>>),
 }
@@ -68,6 +87,12 @@ pub struct Props {
     pub web3: Web3Service,
     pub storage: LocalStorage,
     pub live: Rc<Live>,
+    pub limits: Rc<ChatLimits>,
+    pub modes: ChatModes,
+    pub emotes: Rc<EmotePack>,
+
+    /// Names known by the moderation cache, most recent first.
+    pub names: Rc<Vec<Rc<str>>>,
 }
 
 impl Component for Inputs {
@@ -96,6 +121,10 @@ impl Component for Inputs {
             state: DisplayState::Connect,
 
             temp_msg: String::default(),
+            too_long: false,
+            emote_error: false,
+            picker: false,
+            cooldown: 0,
 
             address: None,
             peer_id: None,
@@ -108,7 +137,15 @@ impl Component for Inputs {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::Set(msg) => self.on_chat_input(msg),
+            Msg::Mention(name) => self.on_mention(name),
+            Msg::Picker => {
+                self.picker = !self.picker;
+
+                true
+            }
+            Msg::Emote(code) => self.on_emote(code),
             Msg::Enter => self.send_message(),
+            Msg::Tick => self.on_tick(),
             Msg::Connect => self.connect_account(),
             Msg::PeerID(res) => self.on_peer_id(res),
             Msg::Account(res) => self.on_account_connected(res),
@@ -122,7 +159,12 @@ impl Component for Inputs {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if !Rc::ptr_eq(&self.props.live, &props.live) {
+        if !Rc::ptr_eq(&self.props.live, &props.live)
+            || !Rc::ptr_eq(&self.props.limits, &props.limits)
+            || self.props.modes != props.modes
+            || !Rc::ptr_eq(&self.props.names, &props.names)
+            || !Rc::ptr_eq(&self.props.emotes, &props.emotes)
+        {
             self.props = props;
 
             #[cfg(debug_assertions)]
@@ -189,24 +231,216 @@ impl Inputs {
             <>
                 <ybc::Field>
                     <ybc::Control>
-                        <ybc::TextArea name="chat_msg" value=String::default() update=self.link.callback(Msg::Set) rows=3 fixed_size=true />
+                        <ybc::TextArea name="chat_msg" value=self.temp_msg.clone() update=self.link.callback(Msg::Set) rows=3 fixed_size=true />
                     </ybc::Control>
+                    { self.render_mentions() }
+                    {
+                        if self.too_long {
+                            html! { <p class="help is-danger"> { format!("Messages are limited to {} characters.", self.props.limits.chat_max_length) } </p> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if self.emote_error {
+                            html! { <p class="help is-danger"> { "Only emotes are allowed right now." } </p> }
+                        } else {
+                            html! {}
+                        }
+                    }
                 </ybc::Field>
-                <ybc::Field>
+                { self.render_modes() }
+                { self.render_picker() }
+                <ybc::Field grouped=true >
                     <ybc::Control>
-                        <ybc::Button classes=classes!("is-primary") onclick=self.link.callback(|_| Msg::Enter)>
-                            { "Send" }
+                        <ybc::Button classes=classes!("is-primary") disabled={self.cooldown > 0} onclick=self.link.callback(|_| Msg::Enter)>
+                        {
+                            if self.cooldown > 0 {
+                                format!("Send ({}s)", self.cooldown)
+                            } else {
+                                "Send".to_owned()
+                            }
+                        }
                         </ybc::Button>
                     </ybc::Control>
+                    {
+                        if self.props.emotes.is_empty() {
+                            html! {}
+                        } else {
+                            html! {
+                                <ybc::Control>
+                                    <ybc::Button classes=classes!("is-light", self.picker.then(|| "is-active")) onclick=self.link.callback(|_| Msg::Picker) >
+                                        <span class="icon"><i class="fas fa-smile"></i></span>
+                                    </ybc::Button>
+                                </ybc::Control>
+                            }
+                        }
+                    }
                 </ybc::Field>
             </>
         }
     }
 
+    /// Known names matching the @mention being typed.
+    fn render_mentions(&self) -> Html {
+        let prefix = match self.mention_prefix() {
+            Some(prefix) => prefix.to_lowercase(),
+            None => return html! {},
+        };
+
+        let mut names: Vec<&Rc<str>> = Vec::with_capacity(5);
+
+        for name in self.props.names.iter() {
+            if names.len() >= 5 {
+                break;
+            }
+
+            if name.to_lowercase().starts_with(&prefix) {
+                names.push(name);
+            }
+        }
+
+        if names.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="buttons are-small">
+            {
+                for names.into_iter().map(|name| {
+                    let mention = name.clone();
+
+                    html! {
+                        <ybc::Button classes=classes!("is-light") onclick=self.link.callback(move |_| Msg::Mention(mention.clone())) >
+                            { format!("@{}", name) }
+                        </ybc::Button>
+                    }
+                })
+            }
+            </div>
+        }
+    }
+
+    /// The last word if it starts with @.
+    fn mention_prefix(&self) -> Option<&str> {
+        if self.temp_msg.ends_with(char::is_whitespace) {
+            return None;
+        }
+
+        let word = self.temp_msg.split_whitespace().last()?;
+
+        word.strip_prefix('@')
+    }
+
+    fn on_mention(&mut self, name: Rc<str>) -> bool {
+        let typed = match self.mention_prefix() {
+            Some(prefix) => prefix.len() + 1,
+            None => return false,
+        };
+
+        let start = self.temp_msg.len() - typed;
+
+        self.temp_msg.truncate(start);
+        self.temp_msg.push('@');
+        self.temp_msg.push_str(&name);
+        self.temp_msg.push(' ');
+
+        true
+    }
+
+    /// Emotes of the streamer's pack.
+    fn render_picker(&self) -> Html {
+        if !self.picker || self.props.emotes.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="buttons are-small" style="max-height: 8em; overflow-y: auto;" >
+            {
+                for self.props.emotes.emotes.iter().map(|(code, image)| {
+                    let emote: Rc<str> = Rc::from(code.as_str());
+
+                    html! {
+                        <ybc::Button classes=classes!("is-white") onclick=self.link.callback(move |_| Msg::Emote(emote.clone())) >
+                            <Image image_cid=image.link ipfs=self.props.ipfs.clone() alt=Some(format!(":{}:", code)) />
+                        </ybc::Button>
+                    }
+                })
+            }
+            </div>
+        }
+    }
+
+    fn on_emote(&mut self, code: Rc<str>) -> bool {
+        if !self.temp_msg.is_empty() && !self.temp_msg.ends_with(char::is_whitespace) {
+            self.temp_msg.push(' ');
+        }
+
+        self.temp_msg.push(':');
+        self.temp_msg.push_str(&code);
+        self.temp_msg.push_str(": ");
+
+        self.too_long =
+            self.temp_msg.trim_end().chars().count() > self.props.limits.chat_max_length;
+        self.emote_error = false;
+
+        true
+    }
+
+    fn render_modes(&self) -> Html {
+        let modes = &self.props.modes;
+
+        if *modes == ChatModes::default() {
+            return html! {};
+        }
+
+        html! {
+            <div class="tags">
+            {
+                if let Some(slow) = modes.slow {
+                    html! { <span class="tag is-warning"> { format!("Slow mode {}s", slow) } </span> }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if modes.friends_only {
+                    html! { <span class="tag is-warning"> { "Friends only" } </span> }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if modes.emote_only {
+                    html! { <span class="tag is-warning"> { "Emote only" } </span> }
+                } else {
+                    html! {}
+                }
+            }
+            </div>
+        }
+    }
+
     /// Send chat message via gossipsub.
     fn send_message(&mut self) -> bool {
+        if self.cooldown > 0 {
+            return false;
+        }
+
         let text = self.temp_msg.clone();
 
+        if text.trim_end().chars().count() > self.props.limits.chat_max_length {
+            self.too_long = true;
+
+            return true;
+        }
+
+        self.emote_error = self.props.modes.emote_only && !is_emote_only(&text, &self.props.emotes);
+
+        if self.emote_error {
+            return true;
+        }
+
         let cid = match self.sign_msg_cid {
             Some(cid) => cid,
             None => {
@@ -243,6 +477,36 @@ impl Inputs {
             }
         });
 
+        self.temp_msg.clear();
+
+        if let Some(slow) = self.props.modes.slow {
+            self.cooldown = slow;
+            self.tick();
+        }
+
+        true
+    }
+
+    fn tick(&self) {
+        spawn_local({
+            let cb = self.link.callback_once(|_| Msg::Tick);
+
+            async move {
+                sleep(Duration::from_secs(1)).await;
+
+                cb.emit(())
+            }
+        });
+    }
+
+    /// Slow mode countdown.
+    fn on_tick(&mut self) -> bool {
+        self.cooldown = self.cooldown.saturating_sub(1);
+
+        if self.cooldown > 0 {
+            self.tick();
+        }
+
         true
     }
 
@@ -271,16 +535,21 @@ impl Inputs {
         false
     }
 
+    /// Always render, the text area and mention suggestions follow the input.
     fn on_chat_input(&mut self, msg: String) -> bool {
+        self.too_long = msg.trim_end().chars().count() > self.props.limits.chat_max_length;
+
         if msg.ends_with('\n') {
             self.temp_msg = msg;
 
-            return self.send_message();
+            self.send_message();
+
+            return true;
         }
 
         self.temp_msg = msg;
 
-        false
+        true
     }
 
     fn on_name_input(&mut self, name: String) -> bool {
@@ -308,7 +577,7 @@ impl Inputs {
     }
 
     /// Callback when Metamask get_eth_accounts return an address.
-    fn on_account_connected(&mut self, response: Result<Address>) -> bool {
+    fn on_account_connected(&mut self, response: Web3Result<Address>) -> bool {
         let address = match response {
             Ok(address) => address,
             Err(e) => {
@@ -334,7 +603,7 @@ impl Inputs {
     }
 
     /// Callback when web3 reverse resolve an address and returns a name.
-    fn on_account_name(&mut self, response: Result<String>) -> bool {
+    fn on_account_name(&mut self, response: Web3Result<String>) -> bool {
         let name = match response {
             Ok(string) => string,
             Err(e) => {
@@ -401,7 +670,7 @@ impl Inputs {
     }
 
     /// Callback when the chat ID has been signed.
-    fn on_signature(&mut self, response: Result<[u8; 65]>) -> bool {
+    fn on_signature(&mut self, response: Web3Result<[u8; 65]>) -> bool {
         let signature = match response {
             Ok(sig) => sig,
             Err(e) => {
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use linked_data::PeerId;
+
+/// Identical messages from the same peer are dropped for this many seconds.
+const DUPLICATE_WINDOW: f64 = 30.0;
+
+/// Chat limits chosen by the streamer.
+///
+/// Optional fields of the live node, absent ones use the defaults.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct ChatLimits {
+    /// Messages per second regained by each peer.
+    pub chat_rate: f64,
+
+    /// Messages a peer can send in a quick burst.
+    pub chat_burst: f64,
+
+    /// Maximum number of characters per message.
+    pub chat_max_length: usize,
+
+    /// Seconds a peer is muted after exceeding the rate.
+    pub chat_mute: f64,
+}
+
+impl ChatLimits {
+    /// Replace values that would block every viewer.
+    pub fn clamped(self) -> Self {
+        Self {
+            chat_rate: self.chat_rate.max(0.01),
+            chat_burst: self.chat_burst.max(1.0),
+            chat_max_length: self.chat_max_length.max(1),
+            chat_mute: self.chat_mute.max(0.0),
+        }
+    }
+}
+
+impl Default for ChatLimits {
+    fn default() -> Self {
+        Self {
+            chat_rate: 0.5,
+            chat_burst: 5.0,
+            chat_max_length: 500,
+            chat_mute: 30.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Accepted,
+    TooLong,
+    Duplicate,
+    /// Too soon in slow mode.
+    Slow,
+    /// Exceeded the rate and is now muted.
+    Muted,
+}
+
+struct Bucket {
+    tokens: f64,
+    /// Seconds
+    updated: f64,
+    /// Seconds
+    muted_until: f64,
+
+    last_text: String,
+    /// Seconds
+    last_time: f64,
+}
+
+/// Per-peer token bucket, duplicate and length checks.
+pub struct FloodGuard {
+    limits: ChatLimits,
+    buckets: HashMap<PeerId, Bucket>,
+
+    /// Seconds between messages in slow mode.
+    slow: Option<u64>,
+}
+
+impl FloodGuard {
+    pub fn new(limits: ChatLimits) -> Self {
+        Self {
+            limits: limits.clamped(),
+            buckets: HashMap::with_capacity(100),
+            slow: None,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: ChatLimits) {
+        self.limits = limits.clamped();
+    }
+
+    pub fn set_slow(&mut self, slow: Option<u64>) {
+        self.slow = slow;
+    }
+
+    pub fn is_muted(&self, peer: &PeerId) -> bool {
+        match self.buckets.get(peer) {
+            Some(bucket) => bucket.muted_until > now(),
+            None => false,
+        }
+    }
+
+    /// Check a text message, consuming a token when accepted.
+    pub fn check(&mut self, peer: PeerId, text: &str) -> Verdict {
+        if text.trim_end().chars().count() > self.limits.chat_max_length {
+            return Verdict::TooLong;
+        }
+
+        let now = now();
+        let limits = self.limits;
+
+        let bucket = self.buckets.entry(peer).or_insert_with(|| Bucket {
+            tokens: limits.chat_burst,
+            updated: now,
+            muted_until: 0.0,
+            last_text: String::new(),
+            last_time: 0.0,
+        });
+
+        if bucket.muted_until > now {
+            return Verdict::Muted;
+        }
+
+        if let Some(interval) = self.slow {
+            if now - bucket.last_time < interval as f64 {
+                return Verdict::Slow;
+            }
+        }
+
+        let elapsed = (now - bucket.updated).max(0.0);
+
+        bucket.tokens = (bucket.tokens + elapsed * limits.chat_rate).min(limits.chat_burst);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            bucket.muted_until = now + limits.chat_mute;
+            bucket.tokens = 0.0;
+
+            return Verdict::Muted;
+        }
+
+        bucket.tokens -= 1.0;
+
+        if bucket.last_text == text && now - bucket.last_time < DUPLICATE_WINDOW {
+            return Verdict::Duplicate;
+        }
+
+        bucket.last_text = text.to_owned();
+        bucket.last_time = now;
+
+        Verdict::Accepted
+    }
+}
+
+/// Seconds since epoch.
+fn now() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cid::{multihash::MultihashGeneric, Cid};
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn peer(name: &str) -> PeerId {
+        Cid::new_v1(0x70, MultihashGeneric::wrap(0x00, name.as_bytes()).unwrap())
+    }
+
+    /// Tokens are regained too slowly to matter during a test.
+    fn limits(chat_burst: f64, chat_max_length: usize) -> ChatLimits {
+        ChatLimits {
+            chat_rate: 0.01,
+            chat_burst,
+            chat_max_length,
+            chat_mute: 30.0,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn burst_then_mute() {
+        let mut guard = FloodGuard::new(limits(3.0, 500));
+        let alice = peer("alice");
+
+        assert_eq!(guard.check(alice, "one"), Verdict::Accepted);
+        assert_eq!(guard.check(alice, "two"), Verdict::Accepted);
+        assert_eq!(guard.check(alice, "three"), Verdict::Accepted);
+        assert_eq!(guard.check(alice, "four"), Verdict::Muted);
+
+        assert!(guard.is_muted(&alice));
+        assert_eq!(guard.check(alice, "five"), Verdict::Muted);
+
+        let bob = peer("bob");
+
+        assert!(!guard.is_muted(&bob));
+        assert_eq!(guard.check(bob, "one"), Verdict::Accepted);
+    }
+
+    #[wasm_bindgen_test]
+    fn duplicates_are_dropped() {
+        let mut guard = FloodGuard::new(limits(5.0, 500));
+        let alice = peer("alice");
+
+        assert_eq!(guard.check(alice, "hello"), Verdict::Accepted);
+        assert_eq!(guard.check(alice, "hello"), Verdict::Duplicate);
+        assert_eq!(guard.check(peer("bob"), "hello"), Verdict::Accepted);
+    }
+
+    #[wasm_bindgen_test]
+    fn length_ignores_trailing_whitespace() {
+        let mut guard = FloodGuard::new(limits(5.0, 5));
+        let alice = peer("alice");
+
+        assert_eq!(guard.check(alice, "123456"), Verdict::TooLong);
+        assert_eq!(guard.check(alice, "12345  "), Verdict::Accepted);
+
+        // Characters are counted, not bytes.
+        assert_eq!(guard.check(alice, "ééééé"), Verdict::Accepted);
+    }
+
+    #[wasm_bindgen_test]
+    fn slow_mode_spaces_messages() {
+        let mut guard = FloodGuard::new(limits(5.0, 500));
+        let alice = peer("alice");
+
+        guard.set_slow(Some(10));
+
+        assert_eq!(guard.check(alice, "one"), Verdict::Accepted);
+        assert_eq!(guard.check(alice, "two"), Verdict::Slow);
+
+        guard.set_slow(None);
+
+        assert_eq!(guard.check(alice, "two"), Verdict::Accepted);
+    }
+
+    #[wasm_bindgen_test]
+    fn clamped_limits_never_block_everyone() {
+        let limits = ChatLimits {
+            chat_rate: 0.0,
+            chat_burst: 0.0,
+            chat_max_length: 0,
+            chat_mute: -1.0,
+        }
+        .clamped();
+
+        assert_eq!(limits.chat_rate, 0.01);
+        assert_eq!(limits.chat_burst, 1.0);
+        assert_eq!(limits.chat_max_length, 1);
+        assert_eq!(limits.chat_mute, 0.0);
+
+        let mut guard = FloodGuard::new(limits);
+
+        assert_eq!(guard.check(peer("alice"), "a"), Verdict::Accepted);
+    }
+}
@@ -1,42 +1,66 @@
 use std::rc::Rc;
 
-use yew::prelude::{html, Component, ComponentLink, Html, Properties, ShouldRender};
+use crate::{
+    components::{chat::emotes::EmotePack, Image},
+    utils::{render_chat_markdown, IpfsService},
+};
 
-use linked_data::mime_type::MimeTyped;
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    Callback, MouseEvent,
+};
+
+use linked_data::{mime_type::MimeTyped, PeerId};
 
 use cid::Cid;
 
 #[derive(Clone)]
 pub struct MessageData {
     pub id: usize,
+    pub peer: PeerId,
+    pub address: [u8; 20],
+    /// Mentions you.
+    pub mention: bool,
     img_data: Rc<str>,
     sender_name: Rc<str>,
     message: Rc<str>,
 }
 
 impl MessageData {
-    pub fn new(id: usize, img_data: &[u8], name: &str, message: &str) -> Self {
+    pub fn new(
+        id: usize,
+        peer: PeerId,
+        address: [u8; 20],
+        img_data: &[u8],
+        name: &str,
+        message: &str,
+        mention: bool,
+    ) -> Self {
         let url = MimeTyped::new("image/png", Cid::default()).data_url(img_data);
 
         Self {
             id,
+            peer,
+            address,
+            mention,
             img_data: Rc::from(url),
             sender_name: Rc::from(name),
             message: Rc::from(message),
         }
     }
 
-    fn render(&self) -> Html {
+    fn render(&self, controls: Html, emote: &dyn Fn(&str) -> Option<Html>) -> Html {
         html! {
-            <article class="message is-small" style="overflow-wrap: break-word" >
+            <article class=classes!("message", "is-small", self.mention.then(|| "is-warning")) style="overflow-wrap: break-word" >
                 <ybc::MessageHeader>
                     <ybc::Image size=ybc::ImageSize::IsSquare >
                         <img src=self.img_data.to_string() height="32" width="32" />
                     </ybc::Image>
                     <h3>{ &self.sender_name }</h3>
+                    { controls }
                 </ybc::MessageHeader>
                 <ybc::MessageBody>
-                    { &self.message }
+                    { render_chat_markdown(&self.message, emote) }
                 </ybc::MessageBody>
             </article>
         }
@@ -46,6 +70,21 @@ impl MessageData {
 #[derive(Clone, Properties)]
 pub struct UIMessage {
     pub message_data: MessageData,
+
+    pub ipfs: IpfsService,
+    pub emotes: Rc<EmotePack>,
+
+    /// Show ban and mod controls.
+    pub moderator: bool,
+
+    /// Show the mod control too.
+    pub promote: bool,
+
+    /// Emit the message id.
+    pub ban_cb: Callback<usize>,
+
+    /// Emit the message id.
+    pub mod_cb: Callback<usize>,
 }
 
 impl Component for UIMessage {
@@ -67,6 +106,9 @@ impl Component for UIMessage {
                 &props.message_data.sender_name,
             )
             || !Rc::ptr_eq(&self.message_data.message, &props.message_data.message)
+            || self.moderator != props.moderator
+            || self.promote != props.promote
+            || !Rc::ptr_eq(&self.emotes, &props.emotes)
         {
             *self = props;
 
@@ -77,6 +119,49 @@ impl Component for UIMessage {
     }
 
     fn view(&self) -> Html {
-        self.message_data.render()
+        let emote = |code: &str| self.render_emote(code);
+
+        self.message_data.render(self.render_controls(), &emote)
+    }
+}
+
+impl UIMessage {
+    fn render_emote(&self, code: &str) -> Option<Html> {
+        let image = self.emotes.get(code)?;
+
+        let html = html! {
+            <Image image_cid=image.link ipfs=self.ipfs.clone() alt=Some(format!(":{}:", code)) />
+        };
+
+        Some(html)
+    }
+
+    fn render_controls(&self) -> Html {
+        if !self.moderator {
+            return html! {};
+        }
+
+        let id = self.message_data.id;
+
+        html! {
+            <div class="buttons are-small" >
+                <ybc::Button classes=classes!("is-danger", "is-outlined") onclick=self.ban_cb.reform(move |_: MouseEvent| id) >
+                    <span class="icon"><i class="fas fa-ban"></i></span>
+                    <span> { "Ban" } </span>
+                </ybc::Button>
+                {
+                    if self.promote {
+                        html! {
+                            <ybc::Button classes=classes!("is-info", "is-outlined") onclick=self.mod_cb.reform(move |_: MouseEvent| id) >
+                                <span class="icon"><i class="fas fa-user-shield"></i></span>
+                                <span> { "Mod" } </span>
+                            </ybc::Button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
     }
 }
@@ -1,6 +1,15 @@
 mod display;
+mod emotes;
 mod inputs;
+mod limits;
 mod message;
+mod moderation;
+mod modes;
 mod window;
 
+pub use display::{ChatEvent, ChatSender, DEFAULT_CHAT_HISTORY};
+pub use emotes::{EmotePack, EMOTE_PACK_PATH};
+pub use limits::ChatLimits;
+pub use moderation::ModerationPanel;
+pub use modes::ChatModes;
 pub use window::ChatWindow;
@@ -0,0 +1,558 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    components::chat::{
+        display::ChatSender,
+        inputs::SIGN_MSG_KEY,
+        modes::{ChatModes, ModeMessage},
+    },
+    utils::{CancelToken, IpfsError, IpfsService, LocalStorage, Web3Service},
+};
+
+use wasm_bindgen_futures::spawn_local;
+
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    services::ConsoleService,
+    Callback, ChangeData, MouseEvent,
+};
+
+use linked_data::{beacon::Beacon, live::Live, moderation::Bans, PeerId};
+
+use web3::types::Address;
+
+use cid::Cid;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+type Web3Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Slow mode choices in seconds.
+const SLOW_MODES: [Option<u64>; 4] = [None, Some(5), Some(30), Some(60)];
+
+/// Review chat participants and bans of the current session.
+///
+/// The streamer can save session bans to their persistent list or remove addresses from it.
+pub struct ModerationPanel {
+    props: Props,
+    link: ComponentLink<Self>,
+
+    token: CancelToken,
+
+    name_cb: Callback<([u8; 20], Web3Result<String>)>,
+    ens_names: HashMap<[u8; 20], String>,
+    resolving: HashSet<[u8; 20]>,
+
+    save_cb: Callback<MouseEvent>,
+    published_cb: Callback<Result<(Cid, Cid, Bans)>>,
+    publishing: bool,
+    error: Option<IpfsError>,
+
+    /// Modes chosen but not yet broadcast.
+    draft: ChatModes,
+    slow_cb: Callback<ChangeData>,
+    friends_only_cb: Callback<MouseEvent>,
+    emote_only_cb: Callback<MouseEvent>,
+    broadcast_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    Name(([u8; 20], Web3Result<String>)),
+    Save,
+    Unban(PeerId),
+    Remove([u8; 20]),
+    Published(Result<(Cid, Cid, Bans)>),
+    Slow(ChangeData),
+    FriendsOnly,
+    EmoteOnly,
+    Broadcast,
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    pub ipfs: IpfsService,
+    pub web3: Web3Service,
+    pub storage: LocalStorage,
+    pub beacon: Option<Rc<Beacon>>,
+
+    /// Only the streamer can update the persistent list.
+    pub streamer: bool,
+
+    /// Moderators and the streamer can change chat modes.
+    pub moderator: bool,
+    pub live: Rc<Live>,
+    pub modes: ChatModes,
+
+    /// Most recent first.
+    pub senders: Rc<VecDeque<ChatSender>>,
+    pub session_bans: Rc<Vec<ChatSender>>,
+    pub bans: Rc<Bans>,
+
+    /// Unbans only apply to your own view of the chat.
+    pub unban_cb: Callback<PeerId>,
+
+    /// Emit the updated list once published.
+    pub bans_cb: Callback<Rc<Bans>>,
+}
+
+impl Component for ModerationPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut comp = Self {
+            token: CancelToken::new(),
+
+            name_cb: link.callback(Msg::Name),
+            ens_names: HashMap::default(),
+            resolving: HashSet::default(),
+
+            save_cb: link.callback(|_event: MouseEvent| Msg::Save),
+            published_cb: link.callback(Msg::Published),
+            publishing: false,
+            error: None,
+
+            draft: props.modes,
+            slow_cb: link.callback(Msg::Slow),
+            friends_only_cb: link.callback(|_event: MouseEvent| Msg::FriendsOnly),
+            emote_only_cb: link.callback(|_event: MouseEvent| Msg::EmoteOnly),
+            broadcast_cb: link.callback(|_event: MouseEvent| Msg::Broadcast),
+
+            props,
+            link,
+        };
+
+        comp.resolve_names();
+
+        comp
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Name((address, result)) => self.on_name(address, result),
+            Msg::Save => self.save_session_bans(),
+            Msg::Unban(peer) => {
+                self.props.unban_cb.emit(peer);
+
+                false
+            }
+            Msg::Remove(address) => self.remove_ban(address),
+            Msg::Published(result) => self.on_published(result),
+            Msg::Slow(data) => match data {
+                ChangeData::Select(element) => {
+                    match SLOW_MODES.get(element.selected_index() as usize) {
+                        Some(slow) => {
+                            self.draft.slow = *slow;
+
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                _ => false,
+            },
+            Msg::FriendsOnly => {
+                self.draft.friends_only = !self.draft.friends_only;
+
+                true
+            }
+            Msg::EmoteOnly => {
+                self.draft.emote_only = !self.draft.emote_only;
+
+                true
+            }
+            Msg::Broadcast => {
+                self.broadcast_modes();
+
+                false
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if !Rc::ptr_eq(&self.props.senders, &props.senders)
+            || !Rc::ptr_eq(&self.props.session_bans, &props.session_bans)
+            || !Rc::ptr_eq(&self.props.bans, &props.bans)
+            || props.beacon.as_ref().map(Rc::as_ptr) != self.props.beacon.as_ref().map(Rc::as_ptr)
+            || props.streamer != self.props.streamer
+            || props.moderator != self.props.moderator
+            || props.modes != self.props.modes
+            || !Rc::ptr_eq(&props.live, &self.props.live)
+        {
+            if props.modes != self.props.modes {
+                self.draft = props.modes;
+            }
+
+            self.props = props;
+
+            self.resolve_names();
+
+            return true;
+        }
+
+        false
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <ybc::Box>
+                <ybc::Title classes=classes!("is-5") > { "Moderation" } </ybc::Title>
+                { self.render_modes() }
+                <ybc::Subtitle size=ybc::HeaderSize::Is6 > { "Recent Senders" } </ybc::Subtitle>
+                { self.render_senders() }
+                <ybc::Subtitle size=ybc::HeaderSize::Is6 > { "Session Bans" } </ybc::Subtitle>
+                { self.render_session_bans() }
+                <ybc::Subtitle size=ybc::HeaderSize::Is6 > { "Persistent Bans" } </ybc::Subtitle>
+                { self.render_bans() }
+                { self.render_error() }
+            </ybc::Box>
+        }
+    }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
+}
+
+impl ModerationPanel {
+    fn render_modes(&self) -> Html {
+        if !self.props.moderator && !self.props.streamer {
+            return html! {};
+        }
+
+        html! {
+            <>
+                <ybc::Subtitle size=ybc::HeaderSize::Is6 > { "Chat Modes" } </ybc::Subtitle>
+                <ybc::Level>
+                    <ybc::LevelLeft>
+                        <ybc::LevelItem>
+                            <div class="select is-small">
+                                <select onchange=self.slow_cb.clone() >
+                                {
+                                    for SLOW_MODES.iter().map(|slow| html! {
+                                        <option selected={self.draft.slow == *slow} >
+                                        {
+                                            match slow {
+                                                Some(seconds) => format!("Slow mode {}s", seconds),
+                                                None => "Slow mode off".to_owned(),
+                                            }
+                                        }
+                                        </option>
+                                    })
+                                }
+                                </select>
+                            </div>
+                        </ybc::LevelItem>
+                        <ybc::LevelItem>
+                            <label class="checkbox">
+                                <input type="checkbox" checked=self.draft.friends_only onclick=self.friends_only_cb.clone() />
+                                { " Friends only" }
+                            </label>
+                        </ybc::LevelItem>
+                        <ybc::LevelItem>
+                            <label class="checkbox">
+                                <input type="checkbox" checked=self.draft.emote_only onclick=self.emote_only_cb.clone() />
+                                { " Emote only" }
+                            </label>
+                        </ybc::LevelItem>
+                        <ybc::LevelItem>
+                            <ybc::Button classes=classes!("is-small", "is-primary") disabled={self.draft == self.props.modes} onclick=self.broadcast_cb.clone() >
+                                { "Apply" }
+                            </ybc::Button>
+                        </ybc::LevelItem>
+                    </ybc::LevelLeft>
+                </ybc::Level>
+            </>
+        }
+    }
+
+    fn render_senders(&self) -> Html {
+        if self.props.senders.is_empty() {
+            return html! { <p class="help"> { "No messages yet." } </p> };
+        }
+
+        html! {
+            <table class="table is-narrow is-fullwidth" >
+                <thead>
+                    <tr>
+                        <th> { "Name" } </th>
+                        <th> { "ENS" } </th>
+                        <th> { "Address" } </th>
+                        <th> { "Peer" } </th>
+                    </tr>
+                </thead>
+                <tbody>
+                {
+                    for self.props.senders.iter().map(|sender| html! {
+                        <tr>
+                            <td> { &sender.name } </td>
+                            <td> { self.ens_names.get(&sender.address).map(String::as_str).unwrap_or("-") } </td>
+                            <td> <code> { format!("{:?}", Address::from(sender.address)) } </code> </td>
+                            <td> <code> { sender.peer.to_string() } </code> </td>
+                        </tr>
+                    })
+                }
+                </tbody>
+            </table>
+        }
+    }
+
+    fn render_session_bans(&self) -> Html {
+        if self.props.session_bans.is_empty() {
+            return html! { <p class="help"> { "No bans this session." } </p> };
+        }
+
+        let save = if self.props.streamer && self.bans_ipns().is_some() {
+            html! {
+                <ybc::Button classes=classes!("is-primary", "is-small") loading=self.publishing disabled=self.publishing onclick=self.save_cb.clone() >
+                    { "Save to ban list" }
+                </ybc::Button>
+            }
+        } else {
+            html! {}
+        };
+
+        html! {
+            <>
+                <table class="table is-narrow is-fullwidth" >
+                    <tbody>
+                    {
+                        for self.props.session_bans.iter().map(|sender| {
+                            let peer = sender.peer;
+
+                            html! {
+                                <tr>
+                                    <td> { &sender.name } </td>
+                                    <td> <code> { format!("{:?}", Address::from(sender.address)) } </code> </td>
+                                    <td>
+                                        <ybc::Button classes=classes!("is-small") onclick=self.link.callback(move |_| Msg::Unban(peer)) >
+                                            { "Unban locally" }
+                                        </ybc::Button>
+                                    </td>
+                                </tr>
+                            }
+                        })
+                    }
+                    </tbody>
+                </table>
+                <p class="help"> { "Unbanning only shows their messages to you, other viewers keep the ban." } </p>
+                { save }
+            </>
+        }
+    }
+
+    fn render_bans(&self) -> Html {
+        if self.props.bans.banned_addrs.is_empty() {
+            return html! { <p class="help"> { "No one is banned." } </p> };
+        }
+
+        let editable = self.props.streamer && self.bans_ipns().is_some();
+
+        html! {
+            <table class="table is-narrow is-fullwidth" >
+                <tbody>
+                {
+                    for self.props.bans.banned_addrs.iter().map(|address| {
+                        let address = *address;
+
+                        html! {
+                            <tr>
+                                <td> { self.ens_names.get(&address).map(String::as_str).unwrap_or("-") } </td>
+                                <td> <code> { format!("{:?}", Address::from(address)) } </code> </td>
+                                <td>
+                                {
+                                    if editable {
+                                        html! {
+                                            <ybc::Button classes=classes!("is-small") disabled=self.publishing onclick=self.link.callback(move |_| Msg::Remove(address)) >
+                                                { "Remove" }
+                                            </ybc::Button>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                </td>
+                            </tr>
+                        }
+                    })
+                }
+                </tbody>
+            </table>
+        }
+    }
+
+    fn render_error(&self) -> Html {
+        match &self.error {
+            Some(e) => html! { <p class="help is-danger"> { e.to_string() } </p> },
+            None => html! {},
+        }
+    }
+
+    /// Send the new modes via gossipsub, signed with your chat identity.
+    ///
+    /// Modes apply once received back, like any other chat message.
+    fn broadcast_modes(&self) {
+        let cid = match self.props.storage.get_cid(SIGN_MSG_KEY) {
+            Some(cid) => cid,
+            None => {
+                #[cfg(debug_assertions)]
+                ConsoleService::error("No Signed Message CID");
+                return;
+            }
+        };
+
+        let msg = ModeMessage {
+            modes: self.draft,
+            signature: cid.into(),
+        };
+
+        let json_string = match serde_json::to_string(&msg) {
+            Ok(json_string) => json_string,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return;
+            }
+        };
+
+        spawn_local({
+            let ipfs = self.props.ipfs.with_token(&self.token);
+            let topic = self.props.live.chat_topic.clone();
+
+            async move {
+                if let Err(e) = ipfs.pubsub_pub(topic, json_string).await {
+                    ConsoleService::error(&format!("{:#?}", e));
+                }
+            }
+        });
+    }
+
+    fn bans_ipns(&self) -> Option<Cid> {
+        self.props.beacon.as_ref().and_then(|beacon| beacon.bans)
+    }
+
+    /// Web3 reverse resolve every address not yet tried.
+    fn resolve_names(&mut self) {
+        let addresses: Vec<[u8; 20]> = self
+            .props
+            .senders
+            .iter()
+            .map(|sender| sender.address)
+            .chain(self.props.bans.banned_addrs.iter().copied())
+            .collect();
+
+        for address in addresses {
+            if !self.resolving.insert(address) {
+                continue;
+            }
+
+            spawn_local({
+                let cb = self.name_cb.clone();
+                let web3 = self.props.web3.clone();
+
+                async move { cb.emit((address, web3.reverse_resolve(Address::from(address)).await)) }
+            });
+        }
+    }
+
+    fn on_name(&mut self, address: [u8; 20], response: Web3Result<String>) -> bool {
+        let name = match response {
+            Ok(name) => name,
+            Err(e) => {
+                #[cfg(debug_assertions)]
+                ConsoleService::warn(&format!("{:?}", e));
+
+                return false;
+            }
+        };
+
+        self.ens_names.insert(address, name);
+
+        true
+    }
+
+    fn save_session_bans(&mut self) -> bool {
+        let mut bans = (*self.props.bans).clone();
+
+        for sender in self.props.session_bans.iter() {
+            bans.banned_addrs.insert(sender.address);
+        }
+
+        self.publish(bans)
+    }
+
+    fn remove_ban(&mut self, address: [u8; 20]) -> bool {
+        let mut bans = (*self.props.bans).clone();
+
+        if !bans.banned_addrs.remove(&address) || !self.publish(bans) {
+            return false;
+        }
+
+        // Lift the bans of this session too.
+        for sender in self.props.session_bans.iter() {
+            if sender.address == address {
+                self.props.unban_cb.emit(sender.peer);
+            }
+        }
+
+        true
+    }
+
+    /// IPFS dag put the new ban list then update its IPNS record.
+    fn publish(&mut self, bans: Bans) -> bool {
+        if self.publishing || !self.props.streamer {
+            return false;
+        }
+
+        let ipns = match self.bans_ipns() {
+            Some(ipns) => ipns,
+            None => return false,
+        };
+
+        spawn_local({
+            let cb = self.published_cb.clone();
+            let ipfs = self.props.ipfs.with_token(&self.token);
+
+            async move {
+                let result = async {
+                    let cid = ipfs.dag_put(&bans).await?;
+
+                    ipfs.name_publish(cid, ipns.to_string()).await?;
+
+                    Ok((ipns, cid, bans))
+                };
+
+                cb.emit(result.await)
+            }
+        });
+
+        self.publishing = true;
+        self.error = None;
+
+        true
+    }
+
+    fn on_published(&mut self, response: Result<(Cid, Cid, Bans)>) -> bool {
+        self.publishing = false;
+
+        let (ipns, cid, bans) = match response {
+            Ok(res) => res,
+            Err(IpfsError::Aborted) => return false,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                self.error = Some(e);
+                return true;
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info("Ban List Published");
+
+        self.props.storage.set_cid(&ipns.to_string(), &cid);
+
+        self.props.bans_cb.emit(Rc::from(bans));
+
+        true
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::chat::emotes::{emote_code, EmotePack};
+
+use linked_data::chat::{ChatMessage, ChatSig};
+
+/// Chat restrictions set by the streamer or a moderator.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct ChatModes {
+    /// Seconds between messages of the same sender.
+    pub slow: Option<u64>,
+
+    /// Only addresses of the streamer's friends may post.
+    pub friends_only: bool,
+
+    /// Messages may only contain emotes.
+    pub emote_only: bool,
+}
+
+/// Mode change broadcast on the chat topic, signed like any chat message.
+#[derive(Serialize, Deserialize)]
+pub struct ModeMessage {
+    pub modes: ChatModes,
+    pub signature: ChatSig,
+}
+
+/// Anything received on the chat topic.
+///
+/// Mode changes are not part of the linked data chat types and are tried second.
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum Payload {
+    Chat(ChatMessage),
+    Modes(ModeMessage),
+}
+
+impl Payload {
+    pub fn signature(&self) -> &ChatSig {
+        match self {
+            Payload::Chat(msg) => &msg.signature,
+            Payload::Modes(msg) => &msg.signature,
+        }
+    }
+}
+
+/// True if every word is an emote code like `:wave:`.
+///
+/// Codes must be part of the pack when the streamer has one.
+pub fn is_emote_only(text: &str, pack: &EmotePack) -> bool {
+    let mut words = text.split_whitespace().peekable();
+
+    if words.peek().is_none() {
+        return false;
+    }
+
+    words.all(|word| match emote_code(word) {
+        Some(code) => pack.is_empty() || pack.get(code).is_some(),
+        None => false,
+    })
+}
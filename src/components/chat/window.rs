@@ -1,15 +1,26 @@
-use std::rc::Rc;
+use std::{collections::HashSet, rc::Rc};
 
 use crate::{
-    components::chat::{display::Display, inputs::Inputs},
+    components::chat::{
+        display::{ChatEvent, Display},
+        emotes::EmotePack,
+        inputs::Inputs,
+        limits::ChatLimits,
+        modes::ChatModes,
+    },
     utils::{IpfsService, LocalStorage, Web3Service},
 };
 
-use yew::prelude::{html, Component, ComponentLink, Html, Properties, ShouldRender};
+use yew::{
+    prelude::{html, Component, ComponentLink, Html, Properties, ShouldRender},
+    Callback,
+};
 
 use linked_data::{
+    friends::Friendlies,
     live::Live,
     moderation::{Bans, Moderators},
+    PeerId,
 };
 
 /// Live chat widget
@@ -18,9 +29,17 @@ pub struct ChatWindow {
     pub web3: Web3Service,
     pub ipfs: IpfsService,
     pub storage: LocalStorage,
+    pub peer_id: PeerId,
     pub live: Rc<Live>,
+    pub limits: Rc<ChatLimits>,
+    pub emotes: Rc<EmotePack>,
+    pub modes: ChatModes,
+    pub names: Rc<Vec<Rc<str>>>,
+    pub friends: Rc<Friendlies>,
     pub mods: Rc<Moderators>,
     pub bans: Rc<Bans>,
+    pub unbanned: Rc<HashSet<PeerId>>,
+    pub event_cb: Callback<ChatEvent>,
 }
 
 impl Component for ChatWindow {
@@ -37,8 +56,14 @@ impl Component for ChatWindow {
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
         if !Rc::ptr_eq(&self.live, &props.live)
+            || !Rc::ptr_eq(&self.limits, &props.limits)
+            || !Rc::ptr_eq(&self.emotes, &props.emotes)
+            || !Rc::ptr_eq(&self.friends, &props.friends)
+            || self.modes != props.modes
+            || !Rc::ptr_eq(&self.names, &props.names)
             || !Rc::ptr_eq(&self.mods, &props.mods)
             || !Rc::ptr_eq(&self.bans, &props.bans)
+            || !Rc::ptr_eq(&self.unbanned, &props.unbanned)
         {
             *self = props;
 
@@ -51,8 +76,8 @@ impl Component for ChatWindow {
     fn view(&self) -> Html {
         html! {
         <>
-            <Display ipfs=self.ipfs.clone() live=self.live.clone() bans=self.bans.clone() mods=self.mods.clone() />
-            <Inputs ipfs=self.ipfs.clone() web3=self.web3.clone() storage=self.storage.clone() live=self.live.clone() />
+            <Display ipfs=self.ipfs.clone() web3=self.web3.clone() storage=self.storage.clone() peer_id=self.peer_id live=self.live.clone() limits=self.limits.clone() emotes=self.emotes.clone() friends=self.friends.clone() bans=self.bans.clone() mods=self.mods.clone() unbanned=self.unbanned.clone() event_cb=self.event_cb.clone() />
+            <Inputs ipfs=self.ipfs.clone() web3=self.web3.clone() storage=self.storage.clone() live=self.live.clone() limits=self.limits.clone() emotes=self.emotes.clone() modes=self.modes names=self.names.clone() />
         </>
         }
     }
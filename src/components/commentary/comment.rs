@@ -2,7 +2,10 @@ use std::rc::Rc;
 
 use crate::components::ExploreCid;
 
-use yew::prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender};
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    Callback, MouseEvent,
+};
 
 use cid::Cid;
 
@@ -11,6 +14,14 @@ pub struct Comment {
     pub cid: Cid,
     pub name: Rc<str>,
     pub comment: Rc<linked_data::comments::Comment>,
+
+    /// Address of the verified signer, none if unverified.
+    pub signer: Option<[u8; 20]>,
+
+    pub replies: usize,
+    pub collapsed: bool,
+    pub reply_cb: Callback<MouseEvent>,
+    pub collapse_cb: Callback<MouseEvent>,
 }
 
 impl Component for Comment {
@@ -26,7 +37,12 @@ impl Component for Comment {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if !Rc::ptr_eq(&self.comment, &props.comment) || !Rc::ptr_eq(&self.name, &props.name) {
+        if !Rc::ptr_eq(&self.comment, &props.comment)
+            || !Rc::ptr_eq(&self.name, &props.name)
+            || self.signer != props.signer
+            || self.replies != props.replies
+            || self.collapsed != props.collapsed
+        {
             *self = props;
 
             return true;
@@ -54,6 +70,9 @@ impl Component for Comment {
                                 <span> { dt } </span>
                             </span>
                         </ybc::Block>
+                        <ybc::Block>
+                            { self.render_signer() }
+                        </ybc::Block>
                         <ybc::Block>
                             <ExploreCid cid=self.cid />
                         </ybc::Block>
@@ -62,9 +81,75 @@ impl Component for Comment {
                         <ybc::Content classes=classes!("has-text-centered") >
                             { &self.comment.comment }
                         </ybc::Content>
+                        { self.render_actions() }
                     </ybc::MediaContent>
                 </ybc::Media>
             </ybc::Box>
         }
     }
 }
+
+impl Comment {
+    fn render_actions(&self) -> Html {
+        let replies = match (self.replies, self.collapsed) {
+            (0, _) => html! {},
+            (count, true) => html! {
+                <ybc::LevelItem>
+                    <a onclick=self.collapse_cb.clone() >
+                        <span class="icon-text">
+                            <span class="icon"><i class="fas fa-chevron-down"></i></span>
+                            <span> { format!("{} {}", count, if count == 1 { "reply" } else { "replies" }) } </span>
+                        </span>
+                    </a>
+                </ybc::LevelItem>
+            },
+            (_, false) => html! {
+                <ybc::LevelItem>
+                    <a onclick=self.collapse_cb.clone() >
+                        <span class="icon-text">
+                            <span class="icon"><i class="fas fa-chevron-up"></i></span>
+                            <span> { "Hide replies" } </span>
+                        </span>
+                    </a>
+                </ybc::LevelItem>
+            },
+        };
+
+        html! {
+            <ybc::Level>
+                <ybc::LevelLeft>
+                    <ybc::LevelItem>
+                        <a onclick=self.reply_cb.clone() >
+                            <span class="icon-text">
+                                <span class="icon"><i class="fas fa-reply"></i></span>
+                                <span> { "Reply" } </span>
+                            </span>
+                        </a>
+                    </ybc::LevelItem>
+                    { replies }
+                </ybc::LevelLeft>
+            </ybc::Level>
+        }
+    }
+
+    fn render_signer(&self) -> Html {
+        match self.signer {
+            Some(address) => {
+                let address: String = address.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+                html! {
+                    <span class="icon-text has-text-success" title=format!("Signed by 0x{}", address) >
+                        <span class="icon"><i class="fas fa-check-circle"></i></span>
+                        <span> { format!("0x{}…", &address[..8]) } </span>
+                    </span>
+                }
+            }
+            None => html! {
+                <span class="tag is-warning" title="This comment signature could not be verified" >
+                    <span class="icon"><i class="fas fa-exclamation-triangle"></i></span>
+                    <span> { "Unverified" } </span>
+                </span>
+            },
+        }
+    }
+}
@@ -1,42 +1,168 @@
-use std::{collections::HashSet, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
-use crate::utils::IpfsService;
+use crate::utils::{
+    current_timestamp, CancelToken, IpfsError, IpfsService, LocalStorage, Priority, Web3Service,
+};
 
 use wasm_bindgen_futures::spawn_local;
 
 use yew::{
-    prelude::{html, Component, ComponentLink, Html, Properties, ShouldRender},
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
     services::ConsoleService,
-    Callback,
+    Callback, ChangeData, MouseEvent,
 };
 
-use linked_data::{comments::Comment, feed::ContentCache};
+use serde::Deserialize;
+
+use linked_data::{
+    beacon::Beacon,
+    comments::{Comment, Commentary},
+    feed::ContentCache,
+    identity::Identity,
+    signature::SignedMessage,
+    IPLDLink,
+};
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use web3::types::Address;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+type Web3Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Number of comments fetched at first and then added by each "load more".
+const COMMENTS_PAGE: usize = 10;
+
+/// Number of replies shown at first and then added by each "load more".
+const REPLIES_PAGE: usize = 3;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum SortType {
+    Newest,
+    Oldest,
+    FriendsFirst,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum FilterType {
+    Everyone,
+    Author,
+    Friends,
+}
+
+/// Comments are signed with their author's Ethereum address, older ones may not be.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum CommentNode {
+    Signed(SignedMessage<Comment>),
+    Unsigned(Comment),
+}
+
+struct CommentData {
+    name: Rc<str>,
+    comment: Rc<Comment>,
+
+    /// Valid signature by this address, not yet matched with the commenter's beacon.
+    address: Option<[u8; 20]>,
+
+    /// Address that signed the comment, none if unverified.
+    signer: Option<[u8; 20]>,
+}
 
 /// Page displaying the content of any media.
 pub struct CommentSection {
     props: Props,
+    link: ComponentLink<Self>,
 
-    comments_cb: Callback<(Cid, Result<Comment>)>,
+    token: CancelToken,
+    comments_cb: Callback<(Cid, Cid, Result<CommentNode>)>,
 
     comments_set: HashSet<Cid>,
-    comments: Vec<(Cid, Rc<str>, Rc<Comment>)>,
+    comments: HashMap<Cid, CommentData>,
+    /// Comments you posted, listed by your beacon once published.
+    posted: HashSet<Cid>,
+
+    /// ENS names of beacons mapped to their address once resolved.
+    author_addrs: HashMap<String, Option<[u8; 20]>>,
+    author_cb: Callback<(String, Web3Result<Address>)>,
+    /// Signers mapped to the beacon of their primary ENS name once resolved.
+    signer_beacons: HashMap<[u8; 20], Option<Cid>>,
+    signer_cb: Callback<([u8; 20], Web3Result<Cid>)>,
+
+    /// Content or comment CID mapped to replies in chronological order.
+    threads: HashMap<Cid, Vec<Cid>>,
+    collapsed: HashSet<Cid>,
+    /// Number of replies shown per thread.
+    pages: HashMap<Cid, usize>,
+
+    sort: SortType,
+    sort_cb: Callback<ChangeData>,
+    filter: FilterType,
+    filter_cb: Callback<ChangeData>,
+    /// Number of comments on the content fetched and shown.
+    page: usize,
+    more_cb: Callback<MouseEvent>,
+
+    show_unverified: bool,
+    unverified_cb: Callback<MouseEvent>,
+
+    reply_to: Option<Cid>,
+    cancel_reply_cb: Callback<MouseEvent>,
+
+    text: String,
+    text_cb: Callback<String>,
+    submit_cb: Callback<MouseEvent>,
+    signed_cb: Callback<(Comment, Web3Result<([u8; 20], [u8; 65])>)>,
+    posted_cb: Callback<(Cid, Cid, Rc<str>, Comment, [u8; 20])>,
+    published_cb: Callback<(Cid, Result<()>)>,
+    publishing: bool,
+    error: Option<String>,
 }
 
 #[derive(Clone, Properties)]
 pub struct Props {
     pub ipfs: IpfsService,
+    pub web3: Web3Service,
+    pub storage: LocalStorage,
 
     pub cid: Cid,
 
     pub content: Rc<ContentCache>,
+
+    /// Beacons mapped to their ENS name.
+    pub ens_names: Rc<HashMap<Cid, String>>,
+
+    /// Comments mapped to the beacon listing them, signatures are checked against it.
+    pub commenters: Rc<HashMap<Cid, Option<Cid>>>,
+
+    /// Display names of your friends.
+    pub friend_names: Rc<HashSet<String>>,
+
+    /// Your beacon, comments are added to its commentary.
+    pub beacon_cid: Option<Cid>,
+    pub beacon: Option<Rc<Beacon>>,
 }
 
 pub enum Msg {
-    Comment((Cid, Result<Comment>)),
+    Comment((Cid, Cid, Result<CommentNode>)),
+    Author((String, Web3Result<Address>)),
+    Signer(([u8; 20], Web3Result<Cid>)),
+    ShowUnverified,
+    Sort(ChangeData),
+    Filter(ChangeData),
+    MoreComments,
+    Collapse(Cid),
+    LoadMore(Cid),
+    Reply(Cid),
+    CancelReply,
+    Text(String),
+    Submit,
+    Signed((Comment, Web3Result<([u8; 20], [u8; 65])>)),
+    Posted((Cid, Cid, Rc<str>, Comment, [u8; 20])),
+    Published((Cid, Result<()>)),
 }
 
 impl Component for CommentSection {
@@ -47,10 +173,45 @@ impl Component for CommentSection {
         let mut comp = Self {
             props,
 
+            token: CancelToken::new(),
             comments_cb: link.callback(Msg::Comment),
 
             comments_set: HashSet::with_capacity(10),
-            comments: Vec::with_capacity(10),
+            comments: HashMap::with_capacity(10),
+            posted: HashSet::default(),
+
+            author_addrs: HashMap::with_capacity(10),
+            author_cb: link.callback(Msg::Author),
+            signer_beacons: HashMap::with_capacity(10),
+            signer_cb: link.callback(Msg::Signer),
+
+            threads: HashMap::with_capacity(10),
+            collapsed: HashSet::default(),
+            pages: HashMap::default(),
+
+            sort: SortType::Newest,
+            sort_cb: link.callback(Msg::Sort),
+            filter: FilterType::Everyone,
+            filter_cb: link.callback(Msg::Filter),
+            page: COMMENTS_PAGE,
+            more_cb: link.callback(|_event: MouseEvent| Msg::MoreComments),
+
+            show_unverified: false,
+            unverified_cb: link.callback(|_event: MouseEvent| Msg::ShowUnverified),
+
+            reply_to: None,
+            cancel_reply_cb: link.callback(|_event: MouseEvent| Msg::CancelReply),
+
+            text: String::default(),
+            text_cb: link.callback(Msg::Text),
+            submit_cb: link.callback(|_event: MouseEvent| Msg::Submit),
+            signed_cb: link.callback(Msg::Signed),
+            posted_cb: link.callback(Msg::Posted),
+            published_cb: link.callback(Msg::Published),
+            publishing: false,
+            error: None,
+
+            link,
         };
 
         comp.get_comments();
@@ -64,83 +225,844 @@ impl Component for CommentSection {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::Comment(result) => self.on_comment(result),
+            Msg::Author((name, result)) => self.on_author(name, result),
+            Msg::Signer((address, result)) => self.on_signer(address, result),
+            Msg::ShowUnverified => {
+                self.show_unverified = !self.show_unverified;
+
+                true
+            }
+            Msg::Sort(data) => self.on_sort(data),
+            Msg::Filter(data) => self.on_filter(data),
+            Msg::MoreComments => {
+                self.page += COMMENTS_PAGE;
+                self.get_comments();
+
+                true
+            }
+            Msg::Collapse(cid) => {
+                if !self.collapsed.remove(&cid) {
+                    self.collapsed.insert(cid);
+                }
+
+                true
+            }
+            Msg::LoadMore(cid) => {
+                *self.pages.entry(cid).or_insert(REPLIES_PAGE) += REPLIES_PAGE;
+                self.get_replies(cid);
+
+                true
+            }
+            Msg::Reply(cid) => {
+                self.reply_to = Some(cid);
+
+                true
+            }
+            Msg::CancelReply => {
+                self.reply_to = None;
+
+                true
+            }
+            Msg::Text(text) => {
+                self.text = text;
+
+                false
+            }
+            Msg::Submit => self.submit(),
+            Msg::Signed((comment, result)) => self.on_signed(comment, result),
+            Msg::Posted((origin, cid, name, comment, address)) => {
+                self.comments_set.insert(cid);
+                self.posted.insert(cid);
+                self.collapsed.remove(&origin);
+
+                // Your own comments are checked against your beacon like any other.
+                let signer = Some(address).filter(|address| self.is_signer(&cid, *address));
+
+                self.insert_comment(origin, cid, name, comment, Some(address), signer);
+
+                true
+            }
+            Msg::Published(result) => self.on_published(result),
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let mut render = false;
+
+        if props.beacon.as_ref().map(Rc::as_ptr) != self.props.beacon.as_ref().map(Rc::as_ptr) {
+            self.props.beacon_cid = props.beacon_cid;
+            self.props.beacon = props.beacon.clone();
+
+            render = true;
+        }
+
+        if !Rc::ptr_eq(&props.ens_names, &self.props.ens_names)
+            || !Rc::ptr_eq(&props.commenters, &self.props.commenters)
+        {
+            self.props.ens_names = props.ens_names.clone();
+            self.props.commenters = props.commenters.clone();
+
+            render |= self.check_authors();
+        }
+
+        if !Rc::ptr_eq(&props.friend_names, &self.props.friend_names) {
+            self.props.friend_names = props.friend_names.clone();
+
+            if self.filter == FilterType::Friends || self.sort == SortType::FriendsFirst {
+                self.get_comments();
+
+                render = true;
+            }
+        }
+
         if !Rc::ptr_eq(&props.content, &self.props.content) {
             self.props = props;
 
             self.get_comments();
         }
 
-        false
+        render
     }
 
     fn view(&self) -> Html {
-        use crate::components::Comment;
-
         html! {
             <ybc::Section>
                 <ybc::Container>
-                {
-                    for self.comments.iter().rev().map(|(cid, name, comment)| {
-                        html! { <Comment cid=*cid name=name.clone() comment=comment.clone() /> }
-                    })
-                }
+                { self.render_composer() }
+                { self.render_options() }
+                { self.render_unverified_toggle() }
+                { self.render_comments() }
                 </ybc::Container>
             </ybc::Section>
         }
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl CommentSection {
-    /// IPFS dag get all comments starting by newest.
-    fn get_comments(&mut self) {
-        if let Some(iterator) = self.props.content.iter_comments(&self.props.cid) {
-            for ipld in iterator {
-                if self.comments_set.insert(*ipld) {
-                    spawn_local({
-                        let ipfs = self.props.ipfs.clone();
-                        let cb = self.comments_cb.clone();
-                        let cid = *ipld;
-
-                        async move { cb.emit((cid, ipfs.dag_get(cid, Option::<String>::None).await)) }
-                    });
-                }
+    fn render_composer(&self) -> Html {
+        if self.props.ipfs.is_read_only() || self.comments_ipns().is_none() {
+            return html! {};
+        }
+
+        let error = match &self.error {
+            Some(e) => html! { <p class="help is-danger"> { e } </p> },
+            None => html! {},
+        };
+
+        let reply = match self.reply_to.and_then(|cid| self.comments.get(&cid)) {
+            Some(data) => html! {
+                <div class="field">
+                    <span class="tag is-info is-light">
+                        { format!("Replying to {}", data.name) }
+                        <button class="delete is-small" onclick=self.cancel_reply_cb.clone() ></button>
+                    </span>
+                </div>
+            },
+            None => html! {},
+        };
+
+        html! {
+            <ybc::Box>
+                { reply }
+                <div class="field">
+                    <div class="control">
+                        <ybc::TextArea name="comment" value=self.text.clone() update=self.text_cb.clone() placeholder="Write a comment..." rows=3 fixed_size=true disabled=self.publishing />
+                    </div>
+                    <p class="help"> { "Comments are signed with your Ethereum wallet." } </p>
+                    { error }
+                </div>
+                <div class="field">
+                    <div class="control">
+                        <ybc::Button classes=classes!("is-primary") loading=self.publishing onclick=self.submit_cb.clone() >
+                            { "Comment" }
+                        </ybc::Button>
+                    </div>
+                </div>
+            </ybc::Box>
+        }
+    }
+
+    fn render_options(&self) -> Html {
+        html! {
+            <ybc::Level>
+                <ybc::LevelLeft>
+                    <ybc::LevelItem>
+                        <div class="select is-small">
+                            <select onchange=self.sort_cb.clone() >
+                                <option selected={self.sort == SortType::Newest} > { "Newest first" } </option>
+                                <option selected={self.sort == SortType::Oldest} > { "Oldest first" } </option>
+                                <option selected={self.sort == SortType::FriendsFirst} > { "Friends first" } </option>
+                            </select>
+                        </div>
+                    </ybc::LevelItem>
+                    <ybc::LevelItem>
+                        <div class="select is-small">
+                            <select onchange=self.filter_cb.clone() >
+                                <option selected={self.filter == FilterType::Everyone} > { "Everyone" } </option>
+                                <option selected={self.filter == FilterType::Author} > { "Author only" } </option>
+                                <option selected={self.filter == FilterType::Friends} > { "Friends only" } </option>
+                            </select>
+                        </div>
+                    </ybc::LevelItem>
+                </ybc::LevelLeft>
+            </ybc::Level>
+        }
+    }
+
+    fn render_unverified_toggle(&self) -> Html {
+        let count = self
+            .comments
+            .values()
+            .filter(|data| data.signer.is_none())
+            .count();
+
+        if count == 0 {
+            return html! {};
+        }
+
+        html! {
+            <ybc::Block>
+                <label class="checkbox">
+                    <input type="checkbox" checked=self.show_unverified onclick=self.unverified_cb.clone() />
+                    { format!(" Show {} unverified comments", count) }
+                </label>
+            </ybc::Block>
+        }
+    }
+
+    /// Visible replies to this content or comment in chronological order.
+    fn visible_replies(&self, origin: &Cid) -> Vec<Cid> {
+        let thread = match self.threads.get(origin) {
+            Some(thread) => thread,
+            None => return Vec::new(),
+        };
+
+        thread
+            .iter()
+            .filter(|cid| match self.comments.get(cid) {
+                Some(data) => self.show_unverified || data.signer.is_some(),
+                None => false,
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Number of visible replies plus those not fetched yet, hidden ones are not counted.
+    fn reply_count(&self, origin: &Cid) -> usize {
+        let unfetched = match self.props.content.iter_comments(origin) {
+            Some(iterator) => iterator
+                .filter(|cid| !self.comments.contains_key(cid))
+                .count(),
+            None => 0,
+        };
+
+        self.visible_replies(origin).len() + unfetched
+    }
+
+    /// Comments on the content, sorted and paged.
+    fn render_comments(&self) -> Html {
+        let candidates = self.candidates();
+        let page: HashSet<&Cid> = candidates.iter().take(self.page).collect();
+
+        let cached: HashSet<Cid> = match self.props.content.iter_comments(&self.props.cid) {
+            Some(iterator) => iterator.copied().collect(),
+            None => HashSet::new(),
+        };
+
+        // Comments you just posted are not cached yet and are always shown.
+        let mut comments = self.visible_replies(&self.props.cid);
+        comments.retain(|cid| page.contains(cid) || !cached.contains(cid));
+
+        match self.sort {
+            SortType::Newest => comments.reverse(),
+            SortType::Oldest => {}
+            SortType::FriendsFirst => {
+                comments.reverse();
+                comments.sort_by_key(|cid| !self.is_friend(cid));
             }
         }
+
+        let remaining = candidates.len().saturating_sub(self.page);
+
+        let load_more = if remaining > 0 {
+            html! {
+                <ybc::Block>
+                    <ybc::Button classes=classes!("is-small") onclick=self.more_cb.clone() >
+                        { format!("Load more comments ({})", remaining) }
+                    </ybc::Button>
+                </ybc::Block>
+            }
+        } else {
+            html! {}
+        };
+
+        html! {
+            <>
+                { for comments.iter().map(|cid| self.render_comment(cid)) }
+                { load_more }
+            </>
+        }
+    }
+
+    /// Replies are shown oldest first and paged.
+    fn render_thread(&self, origin: &Cid) -> Html {
+        let replies = self.visible_replies(origin);
+
+        let limit = self.pages.get(origin).copied().unwrap_or(REPLIES_PAGE);
+        let remaining = self
+            .reply_count(origin)
+            .saturating_sub(replies.len().min(limit));
+
+        let load_more = if remaining > 0 {
+            let origin = *origin;
+
+            html! {
+                <ybc::Block>
+                    <a onclick=self.link.callback(move |_| Msg::LoadMore(origin)) >
+                        { format!("Load more replies ({})", remaining) }
+                    </a>
+                </ybc::Block>
+            }
+        } else {
+            html! {}
+        };
+
+        html! {
+            <>
+                { for replies.iter().take(limit).map(|cid| self.render_comment(cid)) }
+                { load_more }
+            </>
+        }
+    }
+
+    fn render_comment(&self, cid: &Cid) -> Html {
+        use crate::components::Comment;
+
+        let data = match self.comments.get(cid) {
+            Some(data) => data,
+            None => return html! {},
+        };
+
+        let cid = *cid;
+        let replies = self.reply_count(&cid);
+        let collapsed = self.collapsed.contains(&cid);
+
+        let thread = if replies > 0 && !collapsed {
+            html! {
+                <div style="margin-left: 2rem">
+                    { self.render_thread(&cid) }
+                </div>
+            }
+        } else {
+            html! {}
+        };
+
+        html! {
+            <>
+                <Comment cid=cid name=data.name.clone() comment=data.comment.clone() signer=data.signer replies=replies collapsed=collapsed
+                    reply_cb=self.link.callback(move |_| Msg::Reply(cid))
+                    collapse_cb=self.link.callback(move |_| Msg::Collapse(cid)) />
+                { thread }
+            </>
+        }
+    }
+
+    fn comments_ipns(&self) -> Option<Cid> {
+        self.props
+            .beacon
+            .as_ref()
+            .and_then(|beacon| beacon.comments)
+    }
+
+    /// Comments on the content matching the filter in the order they are fetched.
+    ///
+    /// Timestamps are unknown before fetching, commentaries are in chronological order.
+    fn candidates(&self) -> Vec<Cid> {
+        let mut candidates: Vec<Cid> = match self.props.content.iter_comments(&self.props.cid) {
+            Some(iterator) => iterator
+                .filter(|cid| match self.filter {
+                    FilterType::Everyone => true,
+                    FilterType::Author => self.is_author(cid),
+                    FilterType::Friends => self.is_friend(cid),
+                })
+                .copied()
+                .collect(),
+            None => return Vec::new(),
+        };
+
+        match self.sort {
+            SortType::Newest => candidates.reverse(),
+            SortType::Oldest => {}
+            SortType::FriendsFirst => {
+                candidates.reverse();
+                candidates.sort_by_key(|cid| !self.is_friend(cid));
+            }
+        }
+
+        candidates
+    }
+
+    /// The comment is from the content author.
+    fn is_author(&self, cid: &Cid) -> bool {
+        let author = self.props.content.media_content_author(&self.props.cid);
+        let name = self.props.content.comment_author(cid);
+
+        name.is_some() && name == author
+    }
+
+    /// The comment is from one of your friends.
+    fn is_friend(&self, cid: &Cid) -> bool {
+        match self.props.content.comment_author(cid) {
+            Some(name) => self.props.friend_names.contains(name),
+            None => false,
+        }
+    }
+
+    /// IPFS dag get the visible page of comments and their replies.
+    fn get_comments(&mut self) {
+        for cid in self.candidates().into_iter().take(self.page) {
+            self.get_comment(self.props.cid, cid);
+        }
+
+        let origins: Vec<Cid> = self.comments.keys().copied().collect();
+
+        for origin in origins {
+            self.get_replies(origin);
+        }
+    }
+
+    /// IPFS dag get the visible page of replies, oldest first like commentaries.
+    fn get_replies(&mut self, origin: Cid) {
+        let limit = self.pages.get(&origin).copied().unwrap_or(REPLIES_PAGE);
+
+        let replies: Vec<Cid> = match self.props.content.iter_comments(&origin) {
+            Some(iterator) => iterator.take(limit).copied().collect(),
+            None => return,
+        };
+
+        for cid in replies {
+            self.get_comment(origin, cid);
+        }
+    }
+
+    fn get_comment(&mut self, origin: Cid, cid: Cid) {
+        if !self.comments_set.insert(cid) {
+            return;
+        }
+
+        spawn_local({
+            let ipfs = self
+                .props
+                .ipfs
+                .with_priority(Priority::Low)
+                .with_token(&self.token);
+            let cb = self.comments_cb.clone();
+
+            async move { cb.emit((origin, cid, ipfs.dag_get(cid, Option::<String>::None).await)) }
+        });
     }
 
-    fn on_comment(&mut self, response: (Cid, Result<Comment>)) -> bool {
-        let (cid, comment) = match response {
-            (cid, Ok(node)) => (cid, node),
-            (_, Err(e)) => {
+    fn on_sort(&mut self, data: ChangeData) -> bool {
+        let sort = match data {
+            ChangeData::Select(element) => match element.selected_index() {
+                0 => SortType::Newest,
+                1 => SortType::Oldest,
+                2 => SortType::FriendsFirst,
+                _ => return false,
+            },
+            _ => return false,
+        };
+
+        if self.sort == sort {
+            return false;
+        }
+
+        self.sort = sort;
+        self.page = COMMENTS_PAGE;
+        self.get_comments();
+
+        true
+    }
+
+    fn on_filter(&mut self, data: ChangeData) -> bool {
+        let filter = match data {
+            ChangeData::Select(element) => match element.selected_index() {
+                0 => FilterType::Everyone,
+                1 => FilterType::Author,
+                2 => FilterType::Friends,
+                _ => return false,
+            },
+            _ => return false,
+        };
+
+        if self.filter == filter {
+            return false;
+        }
+
+        self.filter = filter;
+        self.page = COMMENTS_PAGE;
+        self.get_comments();
+
+        true
+    }
+
+    fn on_comment(&mut self, response: (Cid, Cid, Result<CommentNode>)) -> bool {
+        let (origin, cid, node) = match response {
+            (origin, cid, Ok(node)) => (origin, cid, node),
+            (_, _, Err(IpfsError::Aborted)) => return false,
+            (_, _, Err(e)) => {
                 ConsoleService::error(&format!("{:?}", e));
                 return false;
             }
         };
 
-        /* if !signed_comment.verify() {
-            return false;
-        } */
+        let (comment, address) = match node {
+            CommentNode::Signed(signed_comment) => {
+                // A valid signature could be replayed on other content.
+                let valid = signed_comment.verify() && signed_comment.data.origin.link == origin;
+
+                #[cfg(debug_assertions)]
+                ConsoleService::info(&format!("Verifiable => {}", valid));
+
+                let address = valid.then(|| signed_comment.address);
 
-        let name = match self.props.content.comment_author(&cid) {
-            Some(name) => name,
+                (signed_comment.data, address)
+            }
+            CommentNode::Unsigned(comment) => (comment, None),
+        };
+
+        let name: Rc<str> = match self.props.content.comment_author(&cid) {
+            Some(name) => Rc::from(name),
             None => return false,
         };
 
-        let index = self
-            .comments
-            .binary_search_by_key(&comment.timestamp, |(_, _, probe)| probe.timestamp)
-            .unwrap_or_else(|x| x);
+        // The signer must also own the beacon listing the comment.
+        let signer = address.filter(|address| self.is_signer(&cid, *address));
+
+        self.insert_comment(origin, cid, name, comment, address, signer);
 
-        self.comments
-            .insert(index, (cid, Rc::from(name), Rc::from(comment)));
+        self.get_replies(cid);
 
         #[cfg(debug_assertions)]
         ConsoleService::info("Content Comments Updated");
 
         true
     }
+
+    /// The address owns the beacon listing this comment, either by the beacon's ENS name
+    /// or by the address' primary ENS name.
+    ///
+    /// Names are resolved in the background, comments are checked again once they are.
+    fn is_signer(&mut self, cid: &Cid, address: [u8; 20]) -> bool {
+        let beacon_cid = match self.props.commenters.get(cid) {
+            Some(Some(beacon_cid)) => *beacon_cid,
+            Some(None) => return false,
+            None if self.posted.contains(cid) => match self.props.beacon_cid {
+                Some(beacon_cid) => beacon_cid,
+                None => return false,
+            },
+            None => return false,
+        };
+
+        if let Some(ens) = self.props.ens_names.get(&beacon_cid).cloned() {
+            if self.author_address(ens) == Some(address) {
+                return true;
+            }
+        }
+
+        self.signer_beacon(address) == Some(beacon_cid)
+    }
+
+    /// Address of this ENS name, resolved in the background the first time.
+    fn author_address(&mut self, ens: String) -> Option<[u8; 20]> {
+        if let Some(address) = self.author_addrs.get(&ens) {
+            return *address;
+        }
+
+        self.author_addrs.insert(ens.clone(), None);
+
+        spawn_local({
+            let cb = self.author_cb.clone();
+            let web3 = self.props.web3.clone();
+
+            async move {
+                let result = web3.name_address(&ens).await;
+
+                cb.emit((ens, result))
+            }
+        });
+
+        None
+    }
+
+    /// Beacon of this address, resolved in the background the first time.
+    fn signer_beacon(&mut self, address: [u8; 20]) -> Option<Cid> {
+        if let Some(beacon_cid) = self.signer_beacons.get(&address) {
+            return *beacon_cid;
+        }
+
+        self.signer_beacons.insert(address, None);
+
+        spawn_local({
+            let cb = self.signer_cb.clone();
+            let web3 = self.props.web3.clone();
+
+            async move { cb.emit((address, web3.address_beacon(Address::from(address)).await)) }
+        });
+
+        None
+    }
+
+    /// Callback when a beacon's ENS name is resolved.
+    fn on_author(&mut self, ens: String, result: Web3Result<Address>) -> bool {
+        let address = match result {
+            Ok(address) => address.to_fixed_bytes(),
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return false;
+            }
+        };
+
+        self.author_addrs.insert(ens, Some(address));
+
+        self.check_authors()
+    }
+
+    /// Callback when a signer's primary ENS name is resolved to a beacon.
+    fn on_signer(&mut self, address: [u8; 20], result: Web3Result<Cid>) -> bool {
+        let beacon_cid = match result {
+            Ok(cid) => cid,
+            Err(_) => return false,
+        };
+
+        self.signer_beacons.insert(address, Some(beacon_cid));
+
+        self.check_authors()
+    }
+
+    /// Match signed comments with the beacon listing them again.
+    fn check_authors(&mut self) -> bool {
+        let pending: Vec<(Cid, [u8; 20])> = self
+            .comments
+            .iter()
+            .filter_map(|(cid, data)| match (data.signer, data.address) {
+                (None, Some(address)) => Some((*cid, address)),
+                _ => None,
+            })
+            .collect();
+
+        let mut render = false;
+
+        for (cid, address) in pending {
+            if !self.is_signer(&cid, address) {
+                continue;
+            }
+
+            if let Some(data) = self.comments.get_mut(&cid) {
+                data.signer = Some(address);
+                render = true;
+            }
+        }
+
+        render
+    }
+
+    fn insert_comment(
+        &mut self,
+        origin: Cid,
+        cid: Cid,
+        name: Rc<str>,
+        comment: Comment,
+        address: Option<[u8; 20]>,
+        signer: Option<[u8; 20]>,
+    ) {
+        let timestamp = comment.timestamp;
+
+        self.comments.insert(
+            cid,
+            CommentData {
+                name,
+                comment: Rc::from(comment),
+                address,
+                signer,
+            },
+        );
+
+        let comments = &self.comments;
+        let thread = self.threads.entry(origin).or_default();
+
+        let index = thread
+            .binary_search_by_key(&timestamp, |probe| match comments.get(probe) {
+                Some(data) => data.comment.timestamp,
+                None => 0,
+            })
+            .unwrap_or_else(|x| x);
+
+        thread.insert(index, cid);
+    }
+
+    fn remove_comment(&mut self, cid: &Cid) {
+        self.comments.remove(cid);
+        self.comments_set.remove(cid);
+        self.posted.remove(cid);
+
+        for thread in self.threads.values_mut() {
+            thread.retain(|probe| probe != cid);
+        }
+    }
+
+    /// Sign the comment with your Ethereum wallet.
+    fn submit(&mut self) -> bool {
+        if self.publishing || self.text.trim().is_empty() || self.comments_ipns().is_none() {
+            return false;
+        }
+
+        let origin = self.reply_to.unwrap_or(self.props.cid);
+
+        let comment = Comment {
+            timestamp: current_timestamp(),
+            origin: IPLDLink { link: origin },
+            comment: self.text.clone(),
+        };
+
+        spawn_local({
+            let cb = self.signed_cb.clone();
+            let web3 = self.props.web3.clone();
+
+            async move {
+                let result = async {
+                    let address = web3.get_eth_accounts().await?;
+
+                    let signature = web3.eth_sign(address, &comment).await?;
+
+                    Ok::<_, Box<dyn std::error::Error>>((address.to_fixed_bytes(), signature))
+                }
+                .await;
+
+                cb.emit((comment, result))
+            }
+        });
+
+        self.publishing = true;
+        self.error = None;
+
+        true
+    }
+
+    /// Add the signed comment, show it right away then update your commentary.
+    fn on_signed(&mut self, comment: Comment, response: Web3Result<([u8; 20], [u8; 65])>) -> bool {
+        let (address, signature) = match response {
+            Ok(res) => res,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                self.publishing = false;
+                self.error = Some(e.to_string());
+                return true;
+            }
+        };
+
+        let (beacon, comments_ipns) = match (&self.props.beacon, self.comments_ipns()) {
+            (Some(beacon), Some(ipns)) => (beacon.clone(), ipns),
+            _ => {
+                self.publishing = false;
+                return true;
+            }
+        };
+
+        let origin = comment.origin.link;
+
+        let signed_comment = SignedMessage {
+            address,
+            data: comment,
+            signature,
+        };
+
+        spawn_local({
+            let ipfs = self.props.ipfs.clone();
+            let storage = self.props.storage.clone();
+            let posted_cb = self.posted_cb.clone();
+            let cb = self.published_cb.clone();
+
+            async move {
+                let (_, identity): (Cid, Identity) =
+                    match ipfs.resolve_and_dag_get(beacon.identity).await {
+                        Ok(res) => res,
+                        Err(e) => return cb.emit((Cid::default(), Err(e))),
+                    };
+
+                let cid = match ipfs.dag_put(&signed_comment).await {
+                    Ok(cid) => cid,
+                    Err(e) => return cb.emit((Cid::default(), Err(e))),
+                };
+
+                posted_cb.emit((
+                    origin,
+                    cid,
+                    Rc::from(identity.display_name),
+                    signed_comment.data,
+                    signed_comment.address,
+                ));
+
+                let result = add_to_commentary(&ipfs, &storage, comments_ipns, origin, cid).await;
+
+                cb.emit((cid, result))
+            }
+        });
+
+        false
+    }
+
+    fn on_published(&mut self, (cid, result): (Cid, Result<()>)) -> bool {
+        self.publishing = false;
+
+        match result {
+            Ok(()) => {
+                self.text.clear();
+                self.reply_to = None;
+
+                #[cfg(debug_assertions)]
+                ConsoleService::info("Comment Published");
+            }
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+
+                // Remove the comment shown before publishing.
+                self.remove_comment(&cid);
+
+                self.error = Some(e.to_string());
+            }
+        }
+
+        true
+    }
+}
+
+/// Link the comment to its origin in your commentary then update its IPNS record.
+async fn add_to_commentary(
+    ipfs: &IpfsService,
+    storage: &LocalStorage,
+    comments_ipns: Cid,
+    origin: Cid,
+    comment: Cid,
+) -> Result<()> {
+    let (_, mut commentary): (Cid, Commentary) = ipfs.resolve_and_dag_get(comments_ipns).await?;
+
+    commentary
+        .comments
+        .entry(origin.to_string())
+        .or_default()
+        .push(IPLDLink { link: comment });
+
+    let commentary_cid = ipfs.dag_put(&commentary).await?;
+
+    ipfs.name_publish(commentary_cid, comments_ipns.to_string())
+        .await?;
+
+    storage.set_cid(&comments_ipns.to_string(), &commentary_cid);
+
+    Ok(())
 }
@@ -1,10 +1,13 @@
-use crate::app::AppRoute;
+use crate::{app::AppRoute, utils::IpfsError};
 
 use yew::prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender};
 
 /// Error indicator.
 #[derive(Clone, Properties)]
-pub struct IPFSConnectionError {}
+pub struct IPFSConnectionError {
+    #[prop_or_default]
+    pub error: Option<IpfsError>,
+}
 
 impl Component for IPFSConnectionError {
     type Message = ();
@@ -18,18 +21,55 @@ impl Component for IPFSConnectionError {
         false
     }
 
-    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if props.error != self.error {
+            *self = props;
+
+            return true;
+        }
+
         false
     }
 
     fn view(&self) -> Html {
+        let help = match &self.error {
+            Some(IpfsError::ConnectionRefused) => {
+                "No IPFS daemon answered. Please verify that your daemon is running.".to_owned()
+            }
+            Some(IpfsError::Blocked) => {
+                "Your IPFS daemon refused this website. Please allow cross-origin (CORS) requests."
+                    .to_owned()
+            }
+            Some(IpfsError::Transport(_)) => {
+                "The connection to IPFS was lost. Please try again.".to_owned()
+            }
+            Some(IpfsError::Daemon { message, .. }) => {
+                format!("Your IPFS daemon returned an error: {}", message)
+            }
+            Some(IpfsError::NotFound(_)) => {
+                "This content could not be found on the network. Please try again later.".to_owned()
+            }
+            Some(IpfsError::Decode(_)) => {
+                "This content is malformed and cannot be displayed.".to_owned()
+            }
+            Some(IpfsError::Aborted) => "The request was cancelled.".to_owned(),
+            Some(IpfsError::Timeout) => {
+                "This content took too long to load, it may not be available right now.".to_owned()
+            }
+            Some(IpfsError::ReadOnly) => {
+                "This feature requires a local IPFS daemon, public gateways are read-only."
+                    .to_owned()
+            }
+            None => "Please verify your connection to IPFS".to_owned(),
+        };
+
         html! {
             <ybc::Container classes=classes!("has-text-centered") >
                 <ybc::Title size=ybc::HeaderSize::Is5 >
                     { "An Error was encounted." }
                 </ybc::Title>
                 <ybc::Subtitle size=ybc::HeaderSize::Is6 >
-                { "Please verify your connection to IPFS" }
+                { help }
                 </ybc::Subtitle>
                 <ybc::ButtonRouter<AppRoute> route=AppRoute::Settings classes=classes!("is-primary") >
                     {"Go to settings"}
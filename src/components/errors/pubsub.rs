@@ -1,10 +1,15 @@
 //use crate::app::AppRoute;
 
+use crate::utils::IpfsError;
+
 use yew::prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender};
 
 /// Error indicator.
 #[derive(Clone, Properties)]
-pub struct IPFSPubSubError {}
+pub struct IPFSPubSubError {
+    #[prop_or_default]
+    pub error: Option<IpfsError>,
+}
 
 impl Component for IPFSPubSubError {
     type Message = ();
@@ -18,7 +23,13 @@ impl Component for IPFSPubSubError {
         false
     }
 
-    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if props.error != self.error {
+            *self = props;
+
+            return true;
+        }
+
         false
     }
 
@@ -31,6 +42,12 @@ impl Component for IPFSPubSubError {
                 <ybc::Subtitle size=ybc::HeaderSize::Is6 >
                 { "Please verify that PubSub is enabled." }
                 </ybc::Subtitle>
+                {
+                    match &self.error {
+                        Some(error) => html! { <p class="help is-danger"> { error.to_string() } </p> },
+                        None => html! {},
+                    }
+                }
             </ybc::Container>
         }
     }
@@ -1,4 +1,4 @@
-use crate::utils::IpfsService;
+use crate::utils::{CancelToken, CatProgress, IpfsError, IpfsService};
 
 use wasm_bindgen_futures::spawn_local;
 
@@ -12,18 +12,25 @@ use linked_data::mime_type::MimeTyped;
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
 
 pub struct Image {
     pub image_cid: Cid,
     pub ipfs: IpfsService,
+    pub alt: Option<String>,
+    pub token: CancelToken,
     pub image_cb: Callback<Result<String>>,
+    pub progress_cb: Callback<CatProgress>,
 
     pub url: String,
+
+    /// Bytes downloaded and total size if known.
+    pub progress: (u64, Option<u64>),
 }
 
 pub enum Msg {
     Data(Result<String>),
+    Progress(CatProgress),
 }
 
 /// Image from IPFS.
@@ -31,6 +38,10 @@ pub enum Msg {
 pub struct Props {
     pub image_cid: Cid,
     pub ipfs: IpfsService,
+
+    /// Render inline, as small as the text, with this alt text shown while loading.
+    #[prop_or_default]
+    pub alt: Option<String>,
 }
 
 impl Component for Image {
@@ -38,13 +49,23 @@ impl Component for Image {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let Props { ipfs, image_cid } = props;
+        let Props {
+            ipfs,
+            image_cid,
+            alt,
+        } = props;
+
+        let token = CancelToken::new();
 
         let comp = Self {
             image_cid,
-            ipfs,
+            ipfs: ipfs.with_token(&token),
+            alt,
+            token,
             image_cb: link.callback(Msg::Data),
+            progress_cb: link.callback(Msg::Progress),
             url: String::default(),
+            progress: (0, None),
         };
 
         comp.get_image_data();
@@ -55,30 +76,66 @@ impl Component for Image {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::Data(result) => self.on_data_url(result),
+            Msg::Progress(progress) => self.on_progress(progress),
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
         if props.image_cid != self.image_cid {
             self.image_cid = props.image_cid;
+            self.progress = (0, None);
 
             self.get_image_data();
         }
 
+        if props.alt != self.alt {
+            self.alt = props.alt;
+
+            return true;
+        }
+
         false
     }
 
     fn view(&self) -> Html {
-        html! {
-            <img src=self.url.clone() />
+        if let Some(alt) = self.alt.as_ref() {
+            if self.url.is_empty() {
+                return html! { <span> { alt } </span> };
+            }
+
+            return html! {
+                <img src=self.url.clone() alt=alt.clone() title=alt.clone() style="height: 1.75em; vertical-align: middle;" />
+            };
+        }
+
+        if !self.url.is_empty() {
+            return html! { <img src=self.url.clone() /> };
+        }
+
+        match self.progress {
+            (loaded, Some(total)) if total > 0 => html! {
+                <progress class="progress is-primary is-small" value=loaded.to_string() max=total.to_string() >
+                    { format!("{}%", loaded * 100 / total) }
+                </progress>
+            },
+            _ => html! {
+                <progress class="progress is-primary is-small">
+                    { "0%" }
+                </progress>
+            },
         }
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl Image {
     fn get_image_data(&self) {
         spawn_local({
             let cb = self.image_cb.clone();
+            let progress_cb = self.progress_cb.clone();
             let ipfs = self.ipfs.clone();
             let cid = self.image_cid;
 
@@ -94,7 +151,10 @@ impl Image {
                     }
                 };
 
-                let data = match ipfs.cid_cat(mime_type.data.link).await {
+                let data = match ipfs
+                    .cat_stream(mime_type.data.link.to_string(), progress_cb)
+                    .await
+                {
                     Ok(mt) => mt,
                     Err(e) => {
                         cb.emit(Err(e));
@@ -107,9 +167,16 @@ impl Image {
         });
     }
 
+    fn on_progress(&mut self, progress: CatProgress) -> bool {
+        self.progress = (progress.loaded, progress.total);
+
+        true
+    }
+
     fn on_data_url(&mut self, result: Result<String>) -> bool {
         let data_url = match result {
             Ok(url) => url,
+            Err(IpfsError::Aborted) => return false,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
                 return false;
@@ -1,4 +1,4 @@
-use crate::utils::{render_markdown, IpfsService};
+use crate::utils::{render_markdown, CancelToken, IpfsError, IpfsService};
 
 use wasm_bindgen_futures::spawn_local;
 
@@ -9,10 +9,11 @@ use yew::{
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
 
 /// Markdown renderer.
 pub struct Markdown {
+    token: CancelToken,
     text: String,
 }
 
@@ -31,15 +32,18 @@ impl Component for Markdown {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let token = CancelToken::new();
+
         spawn_local({
             let cb = link.callback_once(Msg::File);
-            let ipfs = props.ipfs.clone();
+            let ipfs = props.ipfs.with_token(&token);
             let cid = props.markdown_cid;
 
             async move { cb.emit(ipfs.cid_cat(cid).await) }
         });
 
         Self {
+            token,
             text: String::default(),
         }
     }
@@ -57,12 +61,17 @@ impl Component for Markdown {
     fn view(&self) -> Html {
         render_markdown(&self.text)
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl Markdown {
     fn update_file(&mut self, response: Result<Vec<u8>>) -> bool {
         let data = match response {
             Ok(data) => data,
+            Err(IpfsError::Aborted) => return false,
             Err(e) => {
                 ConsoleService::error(&format!("{:#?}", e));
                 return false;
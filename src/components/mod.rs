@@ -7,10 +7,14 @@ mod image;
 mod loading;
 mod md_renderer;
 mod navbar;
+mod pin_button;
+mod read_only;
 mod thumbnail;
 mod video_player;
 
-pub use chat::ChatWindow;
+pub use chat::{
+    ChatEvent, ChatLimits, ChatModes, ChatSender, ChatWindow, ModerationPanel, DEFAULT_CHAT_HISTORY,
+};
 //pub use cid_clipboard::CidClipboard;
 pub use commentary::{Comment, CommentSection};
 pub use errors::{IPFSConnectionError, IPFSPubSubError};
@@ -19,5 +23,7 @@ pub use image::Image;
 pub use loading::Loading;
 pub use md_renderer::Markdown;
 pub use navbar::Navbar;
+pub use pin_button::PinButton;
+pub use read_only::ReadOnlyNotice;
 pub use thumbnail::Thumbnail;
 pub use video_player::VideoPlayer;
@@ -47,6 +47,12 @@ impl Component for Navbar {
                         <span> {"Content Feed"} </span>
                     </span>
                 </Anchor>
+                <Anchor classes="navbar-item" route=AppRoute::Compose>
+                    <span class="icon-text">
+                        <span class="icon"><i class="fas fa-pen"></i></span>
+                        <span> {"Compose"} </span>
+                    </span>
+                </Anchor>
                 <Anchor classes="navbar-item" route=AppRoute::Live>
                     <span class="icon-text">
                         <span class="icon"><i class="fas fa-broadcast-tower"></i></span>
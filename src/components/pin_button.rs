@@ -0,0 +1,236 @@
+use crate::utils::{CancelToken, IpfsError, IpfsService, LocalStorage, Seeded};
+
+use wasm_bindgen_futures::spawn_local;
+
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    services::ConsoleService,
+    Callback, MouseEvent,
+};
+
+use cid::Cid;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+enum State {
+    Unpinned,
+    /// Blocks fetched and total, if known.
+    Pinning(u64, Option<u64>),
+    Pinned,
+    Error(IpfsError),
+}
+
+/// Recursively pin content on the local node to help seed it.
+///
+/// Pinning the metadata node also pins everything it links to,
+/// video segments, images and markdown included.
+pub struct PinButton {
+    props: Props,
+
+    token: CancelToken,
+
+    pin_cb: Callback<MouseEvent>,
+    total_cb: Callback<u64>,
+    progress_cb: Callback<u64>,
+    pinned_cb: Callback<Result<()>>,
+
+    state: State,
+}
+
+pub enum Msg {
+    Pin,
+    Total(u64),
+    Progress(u64),
+    Pinned(Result<()>),
+}
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub ipfs: IpfsService,
+    pub storage: LocalStorage,
+    pub cid: Cid,
+
+    /// Name shown in the list of seeded content.
+    pub title: String,
+}
+
+impl Component for PinButton {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut comp = Self {
+            props,
+
+            token: CancelToken::new(),
+
+            pin_cb: link.callback(|_event: MouseEvent| Msg::Pin),
+            total_cb: link.callback(Msg::Total),
+            progress_cb: link.callback(Msg::Progress),
+            pinned_cb: link.callback(Msg::Pinned),
+
+            state: State::Unpinned,
+        };
+
+        comp.state = comp.initial_state();
+
+        comp
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Pin => self.pin(),
+            Msg::Total(blocks) => self.on_total(blocks),
+            Msg::Progress(blocks) => self.on_progress(blocks),
+            Msg::Pinned(result) => self.on_pinned(result),
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if props.cid != self.props.cid {
+            self.token.cancel();
+            self.token = CancelToken::new();
+
+            self.props = props;
+            self.state = self.initial_state();
+
+            return true;
+        }
+
+        false
+    }
+
+    fn view(&self) -> Html {
+        if self.props.ipfs.is_read_only() {
+            return html! {};
+        }
+
+        let (icon, label) = match &self.state {
+            State::Unpinned => ("fas fa-seedling", "Seed this content".to_owned()),
+            State::Pinning(blocks, Some(total)) => (
+                "fas fa-spinner fa-pulse",
+                format!("{} / {} blocks fetched", blocks, total),
+            ),
+            State::Pinning(blocks, None) => (
+                "fas fa-spinner fa-pulse",
+                format!("{} blocks fetched", blocks),
+            ),
+            State::Pinned => ("fas fa-check", "Seeding".to_owned()),
+            State::Error(_) => ("fas fa-exclamation-triangle", "Retry seeding".to_owned()),
+        };
+
+        let help = match &self.state {
+            State::Error(e) => html! { <p class="help is-danger"> { e.to_string() } </p> },
+            _ => html! {},
+        };
+
+        html! {
+            <>
+                <ybc::Button classes=classes!("is-small", "is-outlined", "is-primary") disabled=matches!(self.state, State::Pinning(..) | State::Pinned) onclick=self.pin_cb.clone() >
+                    <span class="icon"><i class=icon></i></span>
+                    <span> { label } </span>
+                </ybc::Button>
+                { help }
+            </>
+        }
+    }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
+}
+
+impl PinButton {
+    fn initial_state(&self) -> State {
+        let cid = self.props.cid.to_string();
+
+        if self
+            .props
+            .storage
+            .get_seeded()
+            .iter()
+            .any(|item| item.cid == cid)
+        {
+            State::Pinned
+        } else {
+            State::Unpinned
+        }
+    }
+
+    fn pin(&mut self) -> bool {
+        if let State::Pinning(..) | State::Pinned = self.state {
+            return false;
+        }
+
+        spawn_local({
+            let cb = self.pinned_cb.clone();
+            let progress_cb = self.progress_cb.clone();
+            let ipfs = self.props.ipfs.with_token(&self.token);
+            let cid = self.props.cid;
+
+            async move { cb.emit(ipfs.pin_add(cid, true, progress_cb).await) }
+        });
+
+        // Stat walks the whole DAG, the total is shown whenever it arrives.
+        spawn_local({
+            let cb = self.total_cb.clone();
+            let ipfs = self.props.ipfs.with_token(&self.token);
+            let cid = self.props.cid;
+
+            async move {
+                if let Ok(total) = ipfs.dag_stat(cid).await {
+                    cb.emit(total)
+                }
+            }
+        });
+
+        self.state = State::Pinning(0, None);
+
+        true
+    }
+
+    fn on_total(&mut self, blocks: u64) -> bool {
+        match &mut self.state {
+            State::Pinning(_, total) => *total = Some(blocks),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn on_progress(&mut self, blocks: u64) -> bool {
+        match &mut self.state {
+            State::Pinning(fetched, _) => *fetched = blocks,
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn on_pinned(&mut self, result: Result<()>) -> bool {
+        self.state = match result {
+            Ok(()) => {
+                let cid = self.props.cid.to_string();
+                let mut seeded = self.props.storage.get_seeded();
+
+                if !seeded.iter().any(|item| item.cid == cid) {
+                    seeded.push(Seeded {
+                        cid,
+                        title: self.props.title.clone(),
+                    });
+
+                    self.props.storage.set_seeded(&seeded);
+                }
+
+                State::Pinned
+            }
+            Err(IpfsError::Aborted) => return false,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                State::Error(e)
+            }
+        };
+
+        true
+    }
+}
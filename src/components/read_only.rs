@@ -0,0 +1,41 @@
+use crate::app::AppRoute;
+
+use yew::prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender};
+use yew_router::components::RouterAnchor;
+
+type Anchor = RouterAnchor<AppRoute>;
+
+/// Warning displayed when content is read from public gateways.
+#[derive(Clone, Properties)]
+pub struct ReadOnlyNotice {}
+
+impl Component for ReadOnlyNotice {
+    type Message = ();
+    type Properties = Self;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        props
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+        false
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class=classes!("notification", "is-warning", "is-light") >
+                <span class="icon-text">
+                    <span class="icon"><i class="fas fa-eye"></i></span>
+                    <span> { "Read-only mode. No local IPFS node was found, content is loaded from public gateways. Chat and publishing are disabled." } </span>
+                </span>
+                <Anchor route=AppRoute::Settings >
+                    { " Configure IPFS" }
+                </Anchor>
+            </div>
+        }
+    }
+}
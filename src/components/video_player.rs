@@ -2,14 +2,18 @@ use std::{
     collections::VecDeque,
     rc::Rc,
     str::{self, FromStr},
+    time::Duration,
 };
 
 use crate::{
-    components::IPFSPubSubError,
-    utils::{seconds_to_timecode, ExponentialMovingAverage, IpfsService},
+    components::{IPFSConnectionError, IPFSPubSubError},
+    utils::{
+        seconds_to_timecode, CancelToken, CatProgress, ExponentialMovingAverage, IpfsError,
+        IpfsService, Priority, PubsubState,
+    },
 };
 
-use futures_util::future::AbortHandle;
+use futures_util::{future::AbortHandle, join};
 
 use wasm_bindgen::{closure::Closure, JsCast};
 use wasm_bindgen_futures::spawn_local;
@@ -32,11 +36,14 @@ use either::Either;
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
 
 const FORWARD_BUFFER_LENGTH: f64 = 16.0;
 const BACK_BUFFER_LENGTH: f64 = 8.0;
 
+/// Segments taking longer are dropped, a lower quality is tried instead.
+const SEGMENT_TIMEOUT: Duration = Duration::from_secs(10);
+
 const SETUP_PATH: &str = "/time/hour/0/minute/0/second/0/video/setup";
 
 //TODO state machine implicit in the type system
@@ -61,16 +68,21 @@ struct LiveStream {
     data: Rc<Live>,
 
     pubsub_cb: Callback<Result<(PeerId, Vec<u8>)>>,
+    state_cb: Callback<PubsubState>,
     buffer: VecDeque<Cid>,
 
+    /// Seconds until the next subscription attempt.
+    reconnecting: Option<u64>,
+
     handle: AbortHandle,
 }
 
 /// Video player for live streams and on demand.
 pub struct VideoPlayer {
     ipfs: IpfsService,
+    token: CancelToken,
 
-    error: bool,
+    error: Option<IpfsError>,
 
     player_type: Either<LiveStream, Rc<VideoMetadata>>,
 
@@ -85,6 +97,12 @@ pub struct VideoPlayer {
     setup_cb: Callback<Result<SetupNode>>,
     append_cb: Callback<Result<(Vec<u8>, Vec<u8>)>>,
     append_video_cb: Callback<Result<Vec<u8>>>,
+    segment_cb: Callback<Result<Vec<u8>>>,
+    chunk_cb: Callback<CatProgress>,
+
+    /// Video chunks waiting for the source buffer.
+    pending: VecDeque<Vec<u8>>,
+    streaming: bool,
 
     /// Level >= 1 since 0 is audio
     level: usize,
@@ -106,7 +124,10 @@ pub enum Msg {
     SetupNode(Result<SetupNode>),
     Append(Result<(Vec<u8>, Vec<u8>)>),
     AppendVideo(Result<Vec<u8>>),
+    Segment(Result<Vec<u8>>),
+    Chunk(CatProgress),
     PubSub(Result<(PeerId, Vec<u8>)>),
+    PubSubState(PubsubState),
 }
 
 #[derive(Clone, Properties)]
@@ -125,6 +146,14 @@ impl Component for VideoPlayer {
             beacon_or_metadata,
         } = props;
 
+        let token = CancelToken::new();
+
+        // Playback segments go ahead of everything else.
+        let ipfs = ipfs
+            .with_priority(Priority::High)
+            .with_timeout(SEGMENT_TIMEOUT)
+            .with_token(&token);
+
         let ema = ExponentialMovingAverage::new();
 
         let media_source = match MediaSource::new() {
@@ -155,7 +184,9 @@ impl Component for VideoPlayer {
                 let live = LiveStream {
                     data,
                     pubsub_cb: link.callback(Msg::PubSub),
+                    state_cb: link.callback(Msg::PubSubState),
                     buffer: VecDeque::with_capacity(5),
+                    reconnecting: None,
                     handle,
                 };
 
@@ -164,8 +195,9 @@ impl Component for VideoPlayer {
                         let ipfs = ipfs.clone();
                         let topic = live.data.video_topic.clone();
                         let cb = live.pubsub_cb.clone();
+                        let state_cb = live.state_cb.clone();
 
-                        async move { ipfs.pubsub_sub(topic, cb, regis).await }
+                        async move { ipfs.pubsub_sub(topic, cb, state_cb, regis).await }
                     });
                 }
 
@@ -176,8 +208,9 @@ impl Component for VideoPlayer {
 
         Self {
             ipfs,
+            token,
 
-            error: false,
+            error: None,
 
             player_type,
 
@@ -192,6 +225,11 @@ impl Component for VideoPlayer {
             setup_cb: link.callback(Msg::SetupNode),
             append_cb: link.callback(Msg::Append),
             append_video_cb: link.callback(Msg::AppendVideo),
+            segment_cb: link.callback(Msg::Segment),
+            chunk_cb: link.callback(Msg::Chunk),
+
+            pending: VecDeque::with_capacity(10),
+            streaming: false,
 
             level: 1, // start at 1 since 0 is audio
             state: MachineState::Timeout,
@@ -211,10 +249,18 @@ impl Component for VideoPlayer {
             Msg::Seeking => self.on_seeking(),
             Msg::UpdateEnd => self.on_update_end(),
             Msg::Timeout => self.on_timeout(),
-            Msg::SetupNode(result) => self.add_source_buffer(result),
+            Msg::SetupNode(Err(e)) => {
+                ConsoleService::error(&format!("{:?}", e));
+                self.error = Some(e);
+                return true;
+            }
+            Msg::SetupNode(Ok(setup_node)) => self.add_source_buffer(setup_node),
             Msg::Append(result) => self.append_buffers(result),
             Msg::AppendVideo(result) => self.append_video_buffer(result),
+            Msg::Segment(result) => self.on_segment(result),
+            Msg::Chunk(progress) => self.on_chunk(progress),
             Msg::PubSub(result) => return self.on_pubsub_update(result),
+            Msg::PubSubState(state) => return self.on_pubsub_state(state),
         }
 
         false
@@ -248,8 +294,9 @@ impl Component for VideoPlayer {
                 let ipfs = self.ipfs.clone();
                 let topic = live.data.video_topic.clone();
                 let cb = live.pubsub_cb.clone();
+                let state_cb = live.state_cb.clone();
 
-                async move { ipfs.pubsub_sub(topic, cb, regis).await }
+                async move { ipfs.pubsub_sub(topic, cb, state_cb, regis).await }
             });
         }
 
@@ -260,14 +307,32 @@ impl Component for VideoPlayer {
     }
 
     fn view(&self) -> Html {
-        if self.error {
-            return html! { <IPFSPubSubError /> };
+        if let Some(error) = &self.error {
+            return match self.player_type {
+                Either::Left(_) => html! { <IPFSPubSubError error=Some(error.clone()) /> },
+                Either::Right(_) => html! { <IPFSConnectionError error=Some(error.clone()) /> },
+            };
         }
 
+        let reconnecting = match &self.player_type {
+            Either::Left(live) => live.reconnecting,
+            Either::Right(_) => None,
+        };
+
         html! {
-            <ybc::Image size=ybc::ImageSize::Is16by9>
-                <video class=classes!("has-ratio") src=self.object_url.clone() width=640 height=360 id="video_player" autoplay="true" controls=true />
-            </ybc::Image>
+            <>
+                <ybc::Image size=ybc::ImageSize::Is16by9>
+                    <video class=classes!("has-ratio") src=self.object_url.clone() width=640 height=360 id="video_player" autoplay="true" controls=true />
+                </ybc::Image>
+                {
+                    match reconnecting {
+                        Some(seconds) => html! {
+                            <p class="help is-warning"> { format!("Stream disconnected, reconnecting in {}s...", seconds) } </p>
+                        },
+                        None => html! {},
+                    }
+                }
+            </>
         }
     }
 
@@ -324,6 +389,8 @@ impl Component for VideoPlayer {
     }
 
     fn destroy(&mut self) {
+        self.token.cancel();
+
         if let Either::Left(live) = &mut self.player_type {
             live.handle.abort();
         }
@@ -371,8 +438,7 @@ impl VideoPlayer {
             Ok(res) => res,
             Err(e) => {
                 ConsoleService::error(&format!("{:?}", e));
-                self.error = true;
-                return true;
+                return false;
             }
         };
 
@@ -432,11 +498,35 @@ impl VideoPlayer {
         false
     }
 
+    /// Callback when the subscription connects, drops or fails.
+    fn on_pubsub_state(&mut self, state: PubsubState) -> bool {
+        let live = match &mut self.player_type {
+            Either::Left(live) => live,
+            _ => return false,
+        };
+
+        match state {
+            PubsubState::Connected => live.reconnecting = None,
+            PubsubState::Reconnecting(seconds) => live.reconnecting = Some(seconds),
+            PubsubState::Failed(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                live.reconnecting = None;
+                self.error = Some(e);
+            }
+        }
+
+        true
+    }
+
     /// Callback when source buffer is done updating.
     fn on_update_end(&mut self) {
         #[cfg(debug_assertions)]
         ConsoleService::info("On Update End");
 
+        if self.streaming || !self.pending.is_empty() {
+            return self.append_next_chunk();
+        }
+
         self.tick()
     }
 
@@ -502,15 +592,7 @@ impl VideoPlayer {
     }
 
     /// Create source buffer then load initialization segment.
-    fn add_source_buffer(&mut self, setup_node: Result<SetupNode>) {
-        let setup_node = match setup_node {
-            Ok(n) => n,
-            Err(e) => {
-                ConsoleService::error(&format!("{:?}", e));
-                return;
-            }
-        };
-
+    fn add_source_buffer(&mut self, setup_node: SetupNode) {
         #[cfg(debug_assertions)]
         ConsoleService::info("Adding Source Buffer");
 
@@ -674,15 +756,7 @@ impl VideoPlayer {
         let audio_path = format!("{}/track/audio", cid_string);
         let video_path = format!("{}/track/{}", cid_string, track_name);
 
-        self.state = MachineState::AdaptativeBitrate;
-        self.ema.start_timer();
-
-        spawn_local({
-            let cb = self.append_cb.clone();
-            let ipfs = self.ipfs.clone();
-
-            async move { cb.emit(ipfs.double_path_cat(audio_path, video_path).await) }
-        });
+        self.stream_segment(audio_path, video_path);
     }
 
     /// Get CID from timecode then fetch video data from ipfs.
@@ -767,19 +841,62 @@ impl VideoPlayer {
             cid_string, hours, minutes, seconds, track_name,
         );
 
+        self.stream_segment(audio_path, video_path);
+    }
+
+    /// Download the audio segment while streaming the video segment into its buffer.
+    fn stream_segment(&mut self, audio_path: String, video_path: String) {
         self.state = MachineState::AdaptativeBitrate;
         self.ema.start_timer();
+        self.streaming = true;
 
         spawn_local({
-            let cb = self.append_cb.clone();
+            let cb = self.segment_cb.clone();
+            let chunk_cb = self.chunk_cb.clone();
             let ipfs = self.ipfs.clone();
 
-            async move { cb.emit(ipfs.double_path_cat(audio_path, video_path).await) }
+            async move {
+                let (audio, video) = join!(
+                    ipfs.cat_stream(audio_path, Callback::noop()),
+                    ipfs.cat_stream(video_path, chunk_cb)
+                );
+
+                // Video was streamed, only audio is left to append.
+                cb.emit(video.and(audio))
+            }
         });
     }
 
-    /// Recalculate download speed then set quality level.
-    fn check_abr(&mut self) {
+    /// Queue video chunk then append if the buffer is ready.
+    fn on_chunk(&mut self, progress: CatProgress) {
+        if !self.streaming {
+            return;
+        }
+
+        if progress.restart {
+            self.pending.clear();
+            self.abort_video_append();
+        }
+
+        self.ema.add_bytes(progress.chunk.len());
+
+        self.pending.push_back(progress.chunk);
+
+        self.append_next_chunk();
+    }
+
+    /// Callback when segment download is complete.
+    fn on_segment(&mut self, response: Result<Vec<u8>>) {
+        self.streaming = false;
+
+        let mut aud_seg = match response {
+            Ok(a) => a,
+            Err(e) => {
+                self.pending.clear();
+                return self.on_segment_error(e, false);
+            }
+        };
+
         let buffers = match self.media_buffers.as_ref() {
             Some(buf) => buf,
             None => {
@@ -789,16 +906,69 @@ impl VideoPlayer {
             }
         };
 
-        let bandwidth = match buffers.tracks.get(self.level) {
-            Some(track) => track.bandwidth as f64,
+        if let Err(e) = buffers.audio.append_buffer_with_u8_array(&mut aud_seg) {
+            ConsoleService::warn(&format!("{:#?}", e));
+        }
+
+        if self.pending.is_empty() && !buffers.video.updating() {
+            return self.tick();
+        }
+
+        self.append_next_chunk();
+    }
+
+    /// Append the oldest pending chunk.
+    ///
+    /// While streaming, the last chunk is held back so that the final update end event
+    /// only fires once the whole segment was appended.
+    fn append_next_chunk(&mut self) {
+        let buffers = match self.media_buffers.as_ref() {
+            Some(buf) => buf,
             None => {
                 #[cfg(debug_assertions)]
-                ConsoleService::error("No Track");
+                ConsoleService::error("No Media Buffers");
+                return;
+            }
+        };
+
+        if buffers.video.updating() || (self.streaming && self.pending.len() < 2) {
+            return;
+        }
+
+        let mut vid_seg = match self.pending.pop_front() {
+            Some(chunk) => chunk,
+            None => return,
+        };
+
+        if let Err(e) = buffers.video.append_buffer_with_u8_array(&mut vid_seg) {
+            ConsoleService::warn(&format!("{:#?}", e));
+        }
+    }
+
+    /// Reset the video buffer parser, dropping any partly appended segment.
+    fn abort_video_append(&self) {
+        let buffers = match self.media_buffers.as_ref() {
+            Some(buf) => buf,
+            None => return,
+        };
+
+        if let Err(e) = buffers.video.abort() {
+            ConsoleService::warn(&format!("{:#?}", e));
+        }
+    }
+
+    /// Recalculate download speed then set quality level.
+    fn check_abr(&mut self) {
+        let buffers = match self.media_buffers.as_ref() {
+            Some(buf) => buf,
+            None => {
+                #[cfg(debug_assertions)]
+                ConsoleService::error("No Media Buffers");
                 return;
             }
         };
 
-        let avg_bitrate = match self.ema.recalculate_average_speed(bandwidth) {
+        let avg_bitrate = match self.ema.recalculate_average_speed() {
             Some(at) => at,
             None => {
                 self.state = MachineState::Status;
@@ -1026,13 +1196,10 @@ impl VideoPlayer {
     }
 
     /// Append audio and video segments to the buffers.
-    fn append_buffers(&self, response: Result<(Vec<u8>, Vec<u8>)>) {
+    fn append_buffers(&mut self, response: Result<(Vec<u8>, Vec<u8>)>) {
         let (mut aud_seg, mut vid_seg) = match response {
             Ok((a, v)) => (a, v),
-            Err(e) => {
-                ConsoleService::error(&format!("{:?}", e));
-                return;
-            }
+            Err(e) => return self.on_segment_error(e, false),
         };
 
         let buffers = match self.media_buffers.as_ref() {
@@ -1054,13 +1221,10 @@ impl VideoPlayer {
     }
 
     /// Append video segments to the buffer.
-    fn append_video_buffer(&self, response: Result<Vec<u8>>) {
+    fn append_video_buffer(&mut self, response: Result<Vec<u8>>) {
         let mut vid_seg = match response {
             Ok(d) => d,
-            Err(e) => {
-                ConsoleService::error(&format!("{:?}", e));
-                return;
-            }
+            Err(e) => return self.on_segment_error(e, true),
         };
 
         let buffers = match self.media_buffers.as_ref() {
@@ -1076,4 +1240,25 @@ impl VideoPlayer {
             ConsoleService::warn(&format!("{:#?}", e));
         }
     }
+
+    /// Fallback to the lowest quality then try again later.
+    fn on_segment_error(&mut self, error: IpfsError, init_segment: bool) {
+        if let IpfsError::Aborted = error {
+            return;
+        }
+
+        ConsoleService::warn(&format!("{:?}", error));
+
+        // Part of the segment may have been appended already.
+        self.abort_video_append();
+
+        self.state = if init_segment || self.level > 1 {
+            self.level = 1;
+            MachineState::Switch
+        } else {
+            MachineState::Status
+        };
+
+        self.set_timeout();
+    }
 }
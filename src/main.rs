@@ -5,6 +5,10 @@ mod components;
 mod pages;
 mod utils;
 
+// Components and IPFS need a window, tests run in a headless browser.
+#[cfg(test)]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
 use crate::{
     app::Props,
     utils::{IpfsService, LocalStorage, Web3Service},
@@ -0,0 +1,540 @@
+use std::rc::Rc;
+
+use crate::{
+    app::AppRoute,
+    components::{Navbar, ReadOnlyNotice},
+    utils::{current_timestamp, render_markdown, AddOptions, IpfsError, IpfsService, LocalStorage},
+};
+
+use wasm_bindgen_futures::spawn_local;
+
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    services::{
+        reader::{FileData, ReaderService, ReaderTask},
+        ConsoleService,
+    },
+    Callback, ChangeData, MouseEvent,
+};
+
+use yew_router::components::RouterAnchor;
+
+use linked_data::{
+    beacon::Beacon,
+    blog::{FullPost, MicroPost},
+    feed::FeedAnchor,
+    mime_type::MimeTyped,
+    IPLDLink,
+};
+
+use cid::Cid;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+type Anchor = RouterAnchor<AppRoute>;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PostType {
+    Blog,
+    Statement,
+}
+
+enum State {
+    Editing,
+    Publishing,
+    Published(Cid),
+    Error(IpfsError),
+}
+
+struct ImageFile {
+    mime_type: String,
+    data: Vec<u8>,
+    data_url: String,
+}
+
+/// Page to write and publish blog posts and statements.
+pub struct Compose {
+    props: Props,
+    link: ComponentLink<Self>,
+
+    post_type: PostType,
+    blog_cb: Callback<MouseEvent>,
+    statement_cb: Callback<MouseEvent>,
+
+    title: String,
+    title_cb: Callback<String>,
+
+    text: String,
+    text_cb: Callback<String>,
+
+    image: Option<ImageFile>,
+    image_cb: Callback<ChangeData>,
+    reader_task: Option<ReaderTask>,
+
+    publish_cb: Callback<MouseEvent>,
+    uploaded: u64,
+    state: State,
+}
+
+pub enum Msg {
+    PostType(PostType),
+    Title(String),
+    Text(String),
+    ImageSelected(ChangeData),
+    ImageLoaded((String, FileData)),
+    Publish,
+    Progress(u64),
+    Published(Result<Cid>),
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
+    pub ipfs: IpfsService,
+    pub storage: LocalStorage,
+    pub read_only: bool,
+    pub beacon: Option<Rc<Beacon>>,
+}
+
+impl Component for Compose {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            props,
+
+            post_type: PostType::Blog,
+            blog_cb: link.callback(|_event: MouseEvent| Msg::PostType(PostType::Blog)),
+            statement_cb: link.callback(|_event: MouseEvent| Msg::PostType(PostType::Statement)),
+
+            title: String::default(),
+            title_cb: link.callback(Msg::Title),
+
+            text: String::default(),
+            text_cb: link.callback(Msg::Text),
+
+            image: None,
+            image_cb: link.callback(Msg::ImageSelected),
+            reader_task: None,
+
+            publish_cb: link.callback(|_event: MouseEvent| Msg::Publish),
+            uploaded: 0,
+            state: State::Editing,
+
+            link,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::PostType(post_type) => {
+                let changed = self.post_type != post_type;
+
+                self.post_type = post_type;
+
+                changed
+            }
+            Msg::Title(title) => {
+                self.title = title;
+
+                true
+            }
+            Msg::Text(text) => {
+                self.text = text;
+
+                true
+            }
+            Msg::ImageSelected(data) => self.on_image_selected(data),
+            Msg::ImageLoaded((mime_type, file)) => self.on_image_loaded(mime_type, file),
+            Msg::Publish => self.publish(),
+            Msg::Progress(bytes) => {
+                self.uploaded = bytes;
+
+                true
+            }
+            Msg::Published(result) => self.on_published(result),
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let changed = match (&props.beacon, &self.props.beacon) {
+            (Some(new), Some(old)) => !Rc::ptr_eq(new, old),
+            (None, None) => false,
+            _ => true,
+        };
+
+        let changed = changed || props.read_only != self.props.read_only;
+
+        self.props = props;
+
+        changed
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <>
+                <Navbar />
+                <ybc::Section>
+                    <ybc::Container>
+                    {
+                        if self.props.read_only {
+                            html! { <ReadOnlyNotice /> }
+                        } else {
+                            html! {
+                                <>
+                                    { self.render_tabs() }
+                                    <ybc::Columns>
+                                        <ybc::Column>
+                                            { self.render_editor() }
+                                        </ybc::Column>
+                                        <ybc::Column>
+                                            { self.render_preview() }
+                                        </ybc::Column>
+                                    </ybc::Columns>
+                                    { self.render_publish() }
+                                </>
+                            }
+                        }
+                    }
+                    </ybc::Container>
+                </ybc::Section>
+            </>
+        }
+    }
+}
+
+impl Compose {
+    fn render_tabs(&self) -> Html {
+        html! {
+            <ybc::Tabs>
+                <li class={if self.post_type == PostType::Blog {"is-active"} else {""}} >
+                    <a onclick=self.blog_cb.clone() >
+                        <span class="icon-text">
+                            <span class="icon"><i class="fas fa-blog"></i></span>
+                            <span> { "Blog Post" } </span>
+                        </span>
+                    </a>
+                </li>
+                <li class={if self.post_type == PostType::Statement {"is-active"} else {""}} >
+                    <a onclick=self.statement_cb.clone() >
+                        <span class="icon-text">
+                            <span class="icon"><i class="fas fa-comment"></i></span>
+                            <span> { "Statement" } </span>
+                        </span>
+                    </a>
+                </li>
+            </ybc::Tabs>
+        }
+    }
+
+    fn render_editor(&self) -> Html {
+        let disabled = matches!(self.state, State::Publishing);
+
+        match self.post_type {
+            PostType::Blog => html! {
+                <>
+                    <div class="field">
+                        <label class="label"> { "Title" } </label>
+                        <div class="control">
+                            <ybc::Input name="post_title" value=self.title.clone() update=self.title_cb.clone() disabled=disabled />
+                        </div>
+                    </div>
+                    <div class="field">
+                        <label class="label"> { "Image" } </label>
+                        <div class="control">
+                            <input class="input" type="file" accept="image/*" onchange=self.image_cb.clone() disabled={disabled || self.reader_task.is_some()} />
+                        </div>
+                    </div>
+                    <div class="field">
+                        <label class="label"> { "Content" } </label>
+                        <div class="control">
+                            <ybc::TextArea name="post_content" value=self.text.clone() update=self.text_cb.clone() rows=20 disabled=disabled />
+                        </div>
+                        <p class="help"> { "Markdown formatted text." } </p>
+                    </div>
+                </>
+            },
+            PostType::Statement => html! {
+                <div class="field">
+                    <label class="label"> { "Statement" } </label>
+                    <div class="control">
+                        <ybc::TextArea name="statement_content" value=self.text.clone() update=self.text_cb.clone() rows=5 disabled=disabled />
+                    </div>
+                </div>
+            },
+        }
+    }
+
+    fn render_preview(&self) -> Html {
+        match self.post_type {
+            PostType::Blog => html! {
+                <ybc::Box>
+                    <ybc::Title>
+                        { &self.title }
+                    </ybc::Title>
+                    {
+                        match &self.image {
+                            Some(image) => html! {
+                                <ybc::Image size=ybc::ImageSize::Is16by9 >
+                                    <img src=image.data_url.clone() />
+                                </ybc::Image>
+                            },
+                            None => html! {},
+                        }
+                    }
+                    <ybc::Content>
+                        { render_markdown(&self.text) }
+                    </ybc::Content>
+                </ybc::Box>
+            },
+            PostType::Statement => html! {
+                <ybc::Box>
+                    <ybc::Content classes=classes!("has-text-centered")>
+                        { &self.text }
+                    </ybc::Content>
+                </ybc::Box>
+            },
+        }
+    }
+
+    fn render_publish(&self) -> Html {
+        let status = match &self.state {
+            State::Editing => html! {},
+            State::Publishing => html! {
+                <p class="help"> { format!("Publishing... {} KiB uploaded", self.uploaded / 1024) } </p>
+            },
+            State::Published(cid) => html! {
+                <p class="help is-success">
+                    { "Published! " }
+                    <Anchor route=AppRoute::Content(*cid) >
+                        { "View post" }
+                    </Anchor>
+                </p>
+            },
+            State::Error(e) => html! { <p class="help is-danger"> { e.to_string() } </p> },
+        };
+
+        let feed = self
+            .props
+            .beacon
+            .as_ref()
+            .and_then(|beacon| beacon.content_feed);
+
+        let help = match feed {
+            Some(_) => html! {},
+            None => html! {
+                <p class="help is-warning"> { "Your beacon has no content feed, posts cannot be published." } </p>
+            },
+        };
+
+        html! {
+            <div class="field">
+                <div class="control">
+                    <ybc::Button classes=classes!("is-primary") loading=matches!(self.state, State::Publishing) disabled={feed.is_none() || !self.is_complete()} onclick=self.publish_cb.clone() >
+                        { "Publish" }
+                    </ybc::Button>
+                </div>
+                { help }
+                { status }
+            </div>
+        }
+    }
+
+    /// Blog posts need a title, an image and some text. Statements only text.
+    fn is_complete(&self) -> bool {
+        if self.text.trim().is_empty() {
+            return false;
+        }
+
+        match self.post_type {
+            PostType::Blog => !self.title.trim().is_empty() && self.image.is_some(),
+            PostType::Statement => true,
+        }
+    }
+
+    fn on_image_selected(&mut self, data: ChangeData) -> bool {
+        let file = match data {
+            ChangeData::Files(files) => match files.get(0) {
+                Some(file) => file,
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        let mime_type = file.type_();
+
+        if !mime_type.starts_with("image/") {
+            ConsoleService::warn(&format!("Not an image {}", mime_type));
+            return false;
+        }
+
+        let cb = self
+            .link
+            .callback(move |file: FileData| Msg::ImageLoaded((mime_type.clone(), file)));
+
+        match ReaderService::read_file(file, cb) {
+            Ok(task) => self.reader_task = Some(task),
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn on_image_loaded(&mut self, mime_type: String, file: FileData) -> bool {
+        self.reader_task = None;
+
+        let data_url = MimeTyped::new(&mime_type, Cid::default()).data_url(&file.content);
+
+        self.image = Some(ImageFile {
+            mime_type,
+            data: file.content,
+            data_url,
+        });
+
+        true
+    }
+
+    fn publish(&mut self) -> bool {
+        if let State::Publishing = self.state {
+            return false;
+        }
+
+        let feed_ipns = match self
+            .props
+            .beacon
+            .as_ref()
+            .and_then(|beacon| beacon.content_feed)
+        {
+            Some(ipns) => ipns,
+            None => return false,
+        };
+
+        if !self.is_complete() {
+            return false;
+        }
+
+        let ipfs = self.props.ipfs.clone();
+        let storage = self.props.storage.clone();
+        let cb = self.link.callback(Msg::Published);
+        let progress_cb = self.link.callback(Msg::Progress);
+        let text = self.text.clone();
+
+        match self.post_type {
+            PostType::Blog => {
+                let image = match &self.image {
+                    Some(image) => (image.mime_type.clone(), image.data.clone()),
+                    None => return false,
+                };
+
+                let title = self.title.clone();
+
+                spawn_local(async move {
+                    let result = async {
+                        let post = upload_blog(&ipfs, title, text, image, progress_cb).await?;
+
+                        append_to_feed(&ipfs, &storage, feed_ipns, post).await
+                    };
+
+                    cb.emit(result.await)
+                });
+            }
+            PostType::Statement => {
+                let post = MicroPost {
+                    timestamp: current_timestamp(),
+                    content: text,
+                };
+
+                spawn_local(async move {
+                    let result = async {
+                        let post = ipfs.dag_put(&post).await?;
+
+                        append_to_feed(&ipfs, &storage, feed_ipns, post).await
+                    };
+
+                    cb.emit(result.await)
+                });
+            }
+        }
+
+        self.uploaded = 0;
+        self.state = State::Publishing;
+
+        true
+    }
+
+    fn on_published(&mut self, result: Result<Cid>) -> bool {
+        self.state = match result {
+            Ok(cid) => {
+                self.title.clear();
+                self.text.clear();
+                self.image = None;
+
+                State::Published(cid)
+            }
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                State::Error(e)
+            }
+        };
+
+        true
+    }
+}
+
+/// Upload markdown and image then add the post node. Return the post CID.
+async fn upload_blog(
+    ipfs: &IpfsService,
+    title: String,
+    markdown: String,
+    (mime_type, image): (String, Vec<u8>),
+    progress_cb: Callback<u64>,
+) -> Result<Cid> {
+    // Leaves hold the markdown bytes unwrapped, long posts are still split in chunks.
+    let options = AddOptions {
+        cid_v1: true,
+        raw_leaves: true,
+    };
+
+    let markdown_size = markdown.len() as u64;
+
+    let content = ipfs
+        .add(markdown.into_bytes(), options, progress_cb.clone())
+        .await?;
+
+    let image_cb = Callback::from(move |bytes: u64| progress_cb.emit(markdown_size + bytes));
+
+    let image = ipfs.add(image, options, image_cb).await?;
+
+    let image = ipfs.dag_put(&MimeTyped::new(&mime_type, image)).await?;
+
+    let post = FullPost {
+        timestamp: current_timestamp(),
+        image: IPLDLink { link: image },
+        content: IPLDLink { link: content },
+        title,
+    };
+
+    ipfs.dag_put(&post).await
+}
+
+/// Append the post to the content feed then update its IPNS record. Return the post CID.
+async fn append_to_feed(
+    ipfs: &IpfsService,
+    storage: &LocalStorage,
+    feed_ipns: Cid,
+    post: Cid,
+) -> Result<Cid> {
+    let (_, mut feed): (Cid, FeedAnchor) = ipfs.resolve_and_dag_get(feed_ipns).await?;
+
+    feed.content.push(IPLDLink { link: post });
+
+    let feed_cid = ipfs.dag_put(&feed).await?;
+
+    ipfs.name_publish(feed_cid, feed_ipns.to_string()).await?;
+
+    storage.set_cid(&feed_ipns.to_string(), &feed_cid);
+
+    Ok(post)
+}
@@ -1,11 +1,16 @@
-use std::rc::Rc;
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use crate::{
     components::{
         CommentSection, ExploreCid, IPFSConnectionError, Image, Loading, Markdown, Navbar,
-        VideoPlayer,
+        PinButton, VideoPlayer,
+    },
+    utils::{
+        timestamp_to_datetime, CancelToken, IpfsError, IpfsService, LocalStorage, Web3Service,
     },
-    utils::{timestamp_to_datetime, IpfsService},
 };
 
 use wasm_bindgen_futures::spawn_local;
@@ -17,6 +22,7 @@ use yew::{
 };
 
 use linked_data::{
+    beacon::Beacon,
     blog::{FullPost, MicroPost},
     feed::{ContentCache, Media},
     video::VideoMetadata,
@@ -26,19 +32,20 @@ use either::Either;
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
 
 #[allow(clippy::large_enum_variant)]
 enum State {
     Loading,
     Ready(Media),
-    Error,
+    Error(IpfsError),
 }
 
 /// Page displaying the content of any media.
 pub struct Content {
     props: Props,
 
+    token: CancelToken,
     content_cb: Callback<Result<Media>>,
 
     state: State,
@@ -48,10 +55,24 @@ pub struct Content {
 #[derive(Clone, Properties)]
 pub struct Props {
     pub ipfs: IpfsService,
+    pub web3: Web3Service,
+    pub storage: LocalStorage,
 
     pub cid: Cid,
 
     pub content: Rc<ContentCache>,
+
+    /// Beacons mapped to their ENS name.
+    pub ens_names: Rc<HashMap<Cid, String>>,
+
+    /// Comments mapped to the beacon listing them.
+    pub commenters: Rc<HashMap<Cid, Option<Cid>>>,
+
+    /// Display names of your friends.
+    pub friend_names: Rc<HashSet<String>>,
+
+    pub beacon_cid: Option<Cid>,
+    pub beacon: Option<Rc<Beacon>>,
 }
 
 pub enum Msg {
@@ -66,6 +87,7 @@ impl Component for Content {
         let mut comp = Self {
             props,
 
+            token: CancelToken::new(),
             content_cb: link.callback(Msg::Metadata),
 
             state: State::Loading,
@@ -87,6 +109,26 @@ impl Component for Content {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let mut render = false;
+
+        if props.beacon.as_ref().map(Rc::as_ptr) != self.props.beacon.as_ref().map(Rc::as_ptr) {
+            self.props.beacon_cid = props.beacon_cid;
+            self.props.beacon = props.beacon.clone();
+
+            render = true;
+        }
+
+        if !Rc::ptr_eq(&props.ens_names, &self.props.ens_names)
+            || !Rc::ptr_eq(&props.commenters, &self.props.commenters)
+            || !Rc::ptr_eq(&props.friend_names, &self.props.friend_names)
+        {
+            self.props.ens_names = props.ens_names.clone();
+            self.props.commenters = props.commenters.clone();
+            self.props.friend_names = props.friend_names.clone();
+
+            render = true;
+        }
+
         if !Rc::ptr_eq(&props.content, &self.props.content) {
             #[cfg(debug_assertions)]
             {
@@ -101,7 +143,7 @@ impl Component for Content {
             self.get_content();
         }
 
-        false
+        render
     }
 
     fn view(&self) -> Html {
@@ -121,15 +163,20 @@ impl Component for Content {
                                 Media::Blog(blog) => self.render_blog(dt, blog),
                                 Media::Statement(twit) => self.render_microblog(dt, twit),
                             }},
-                            State::Error => html! { <IPFSConnectionError /> },
+                            State::Error(error) => html! { <IPFSConnectionError error=Some(error.clone()) /> },
                         }
                     }
                     </ybc::Container>
                 </ybc::Section>
-                <CommentSection ipfs=self.props.ipfs.clone() cid=self.props.cid content=self.props.content.clone() />
+                <CommentSection ipfs=self.props.ipfs.clone() web3=self.props.web3.clone() storage=self.props.storage.clone() cid=self.props.cid content=self.props.content.clone() ens_names=self.props.ens_names.clone() commenters=self.props.commenters.clone()
+                    friend_names=self.props.friend_names.clone() beacon_cid=self.props.beacon_cid beacon=self.props.beacon.clone() />
             </>
         }
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl Content {
@@ -159,6 +206,9 @@ impl Content {
                         <ybc::LevelItem>
                             <ExploreCid cid=self.props.cid />
                         </ybc::LevelItem>
+                        <ybc::LevelItem>
+                            <PinButton ipfs=self.props.ipfs.clone() storage=self.props.storage.clone() cid=self.props.cid title=metadata.title.clone() />
+                        </ybc::LevelItem>
                     </ybc::LevelRight>
                 </ybc::Level>
             </ybc::Box>
@@ -193,6 +243,9 @@ impl Content {
                         <ybc::LevelItem>
                             <ExploreCid cid=self.props.cid />
                         </ybc::LevelItem>
+                        <ybc::LevelItem>
+                            <PinButton ipfs=self.props.ipfs.clone() storage=self.props.storage.clone() cid=self.props.cid title=metadata.title.clone() />
+                        </ybc::LevelItem>
                     </ybc::LevelRight>
                 </ybc::Level>
                 <ybc::Content>
@@ -222,6 +275,9 @@ impl Content {
                         <ybc::Block>
                             <ExploreCid cid=self.props.cid />
                         </ybc::Block>
+                        <ybc::Block>
+                            <PinButton ipfs=self.props.ipfs.clone() storage=self.props.storage.clone() cid=self.props.cid title=Self::statement_title(metadata) />
+                        </ybc::Block>
                     </ybc::MediaLeft>
                     <ybc::MediaContent>
                         <ybc::Content classes=classes!("has-text-centered")>
@@ -233,10 +289,23 @@ impl Content {
         }
     }
 
+    /// Statements have no title, use the beginning of the text instead.
+    fn statement_title(metadata: &MicroPost) -> String {
+        const MAX_CHARS: usize = 50;
+
+        let mut title: String = metadata.content.chars().take(MAX_CHARS).collect();
+
+        if metadata.content.chars().count() > MAX_CHARS {
+            title.push('…');
+        }
+
+        title
+    }
+
     fn get_content(&mut self) {
         spawn_local({
             let cb = self.content_cb.clone();
-            let ipfs = self.props.ipfs.clone();
+            let ipfs = self.props.ipfs.with_token(&self.token);
             let cid = self.props.cid;
 
             async move { cb.emit(ipfs.dag_get(cid, Option::<String>::None).await) }
@@ -251,9 +320,10 @@ impl Content {
     fn on_metadata(&mut self, response: Result<Media>) -> bool {
         self.state = match response {
             Ok(md) => State::Ready(md),
+            Err(IpfsError::Aborted) => return false,
             Err(e) => {
                 ConsoleService::error(&format!("{:#?}", e));
-                State::Error
+                State::Error(e)
             }
         };
 
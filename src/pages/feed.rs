@@ -1,8 +1,8 @@
 use std::{collections::HashSet, rc::Rc};
 
 use crate::{
-    components::{IPFSConnectionError, Loading, Navbar, Thumbnail},
-    utils::{IpfsService, LocalStorage},
+    components::{IPFSConnectionError, Loading, Navbar, ReadOnlyNotice, Thumbnail},
+    utils::{CancelToken, IpfsError, IpfsService, LocalStorage, Priority},
 };
 
 use wasm_bindgen_futures::spawn_local;
@@ -20,7 +20,7 @@ use linked_data::{
 
 use cid::Cid;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, IpfsError>;
 
 pub enum MachineState {
     Connecting,
@@ -42,6 +42,7 @@ pub struct ContentFeed {
 
     state: MachineState,
 
+    token: CancelToken,
     media_cb: Callback<(Cid, Result<Media>)>,
     content_set: HashSet<Cid>,
     content: Vec<(Cid, Rc<str>, Rc<Media>, usize)>,
@@ -64,6 +65,8 @@ pub struct Props {
     pub storage: LocalStorage,
     pub content: Rc<ContentCache>,
     pub peer_id: Option<PeerId>,
+    pub error: Option<IpfsError>,
+    pub read_only: bool,
 }
 
 impl Component for ContentFeed {
@@ -76,6 +79,7 @@ impl Component for ContentFeed {
 
             state: MachineState::Connecting,
 
+            token: CancelToken::new(),
             media_cb: link.callback(Msg::Metadata),
             content_set: HashSet::with_capacity(100),
             content: Vec::with_capacity(100),
@@ -109,22 +113,42 @@ impl Component for ContentFeed {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let mut render = false;
+
         if props.peer_id != self.props.peer_id && props.peer_id.is_some() {
             self.state = MachineState::Loading;
         }
 
+        if props.read_only != self.props.read_only {
+            if props.read_only {
+                self.state = MachineState::Loading;
+            }
+
+            self.props.read_only = props.read_only;
+
+            render = true;
+        }
+
+        if props.error != self.props.error {
+            self.props.error = props.error.clone();
+
+            render = true;
+        }
+
         if !Rc::ptr_eq(&props.content, &self.props.content) {
             self.props = props;
 
             self.get_content();
         }
 
-        false
+        render
     }
 
     fn view(&self) -> Html {
         let content = match self.state {
-            MachineState::Connecting => html! { <IPFSConnectionError /> },
+            MachineState::Connecting => {
+                html! { <IPFSConnectionError error=self.props.error.clone() /> }
+            }
             MachineState::Loading => html! {  <Loading /> },
             MachineState::Loaded => self.render_thumbnails(),
         };
@@ -134,6 +158,13 @@ impl Component for ContentFeed {
                 <Navbar />
                 <ybc::Section>
                     <ybc::Container>
+                        {
+                            if self.props.read_only {
+                                html! { <ReadOnlyNotice /> }
+                            } else {
+                                html! {}
+                            }
+                        }
                         <ybc::Tabs classes=classes!("is-small") toggle=true fullwidth=true >
                             <li class={if let FilterType::None = self.filter {"is-active"} else {""}} >
                                 <a onclick=self.no_filter_cb.clone() >
@@ -174,19 +205,25 @@ impl Component for ContentFeed {
             </>
         }
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl ContentFeed {
     fn render_thumbnails(&self) -> Html {
+        let ipfs = self.props.ipfs.with_priority(Priority::Low);
+
         html! {
             <>
             {
             self.content.iter().rev().filter_map(|(cid, name, metadata, count)| {
                 match (metadata.as_ref(), &self.filter) {
-                    (_, FilterType::None) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=self.props.ipfs.clone() />}),
-                    (Media::Video(_), FilterType::Videos) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=self.props.ipfs.clone() />}),
-                    (Media::Blog(_), FilterType::Blogs) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=self.props.ipfs.clone() />}),
-                    (Media::Statement(_), FilterType::Statements) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=self.props.ipfs.clone() />}),
+                    (_, FilterType::None) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=ipfs.clone() />}),
+                    (Media::Video(_), FilterType::Videos) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=ipfs.clone() />}),
+                    (Media::Blog(_), FilterType::Blogs) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=ipfs.clone() />}),
+                    (Media::Statement(_), FilterType::Statements) => Some(html! { <Thumbnail cid=*cid name=name.clone()  metadata=metadata.clone() count=*count ipfs=ipfs.clone() />}),
                     (_, _) => None,
                 }
             }).collect::<Html>()
@@ -201,7 +238,11 @@ impl ContentFeed {
             if self.content_set.insert(*cid) {
                 spawn_local({
                     let cb = self.media_cb.clone();
-                    let ipfs = self.props.ipfs.clone();
+                    let ipfs = self
+                        .props
+                        .ipfs
+                        .with_priority(Priority::Low)
+                        .with_token(&self.token);
                     let cid = *cid;
 
                     async move { cb.emit((cid, ipfs.dag_get(cid, Option::<&str>::None).await)) }
@@ -214,6 +255,7 @@ impl ContentFeed {
     fn on_metadata(&mut self, response: (Cid, Result<Media>)) -> bool {
         let (cid, metadata) = match response {
             (cid, Ok(metadata)) => (cid, metadata),
+            (_, Err(IpfsError::Aborted)) => return false,
             (_, Err(e)) => {
                 ConsoleService::error(&format!("{:?}", e));
                 return false;
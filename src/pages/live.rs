@@ -1,16 +1,27 @@
-use std::rc::Rc;
+use std::{
+    collections::{HashSet, VecDeque},
+    rc::Rc,
+};
 
 use crate::{
-    components::{ChatWindow, IPFSConnectionError, Navbar, VideoPlayer},
-    utils::{IpfsService, LocalStorage, Web3Service},
+    components::{
+        ChatEvent, ChatLimits, ChatModes, ChatSender, ChatWindow, EmotePack, IPFSConnectionError,
+        ModerationPanel, Navbar, ReadOnlyNotice, VideoPlayer,
+    },
+    utils::{IpfsError, IpfsService, LocalStorage, Web3Service},
 };
 
-use yew::prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender};
+use yew::{
+    prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
+    Callback,
+};
 
 #[cfg(debug_assertions)]
 use yew::services::ConsoleService;
 
 use linked_data::{
+    beacon::Beacon,
+    friends::Friendlies,
     live::Live,
     moderation::{Bans, Moderators},
     PeerId,
@@ -18,40 +29,110 @@ use linked_data::{
 
 use either::Either;
 
-/// Page displaying live video and chat.
-#[derive(Properties, Clone)]
+/// Number of recent senders shown in the moderation panel.
+const RECENT_SENDERS: usize = 20;
+
+/// Page displaying live video, chat and moderation tools.
 pub struct LivePage {
+    props: Props,
+
+    event_cb: Callback<ChatEvent>,
+    unban_cb: Callback<PeerId>,
+    bans_cb: Callback<Rc<Bans>>,
+
+    senders: Rc<VecDeque<ChatSender>>,
+    names: Rc<Vec<Rc<str>>>,
+    session_bans: Rc<Vec<ChatSender>>,
+    unbanned: Rc<HashSet<PeerId>>,
+
+    /// Your ban list, updated locally once published.
+    bans: Rc<Bans>,
+
+    modes: ChatModes,
+
+    /// You are a moderator of this chat.
+    moderator: bool,
+}
+
+pub enum Msg {
+    Chat(ChatEvent),
+    Unban(PeerId),
+    Bans(Rc<Bans>),
+}
+
+#[derive(Properties, Clone)]
+pub struct Props {
     pub peer_id: Option<PeerId>,
+    pub error: Option<IpfsError>,
+    pub read_only: bool,
     pub ipfs: IpfsService,
     pub web3: Web3Service,
     pub storage: LocalStorage,
+    pub beacon: Option<Rc<Beacon>>,
     pub live: Rc<Live>,
+    pub limits: Rc<ChatLimits>,
+    pub emotes: Rc<EmotePack>,
+    pub friends: Rc<Friendlies>,
     pub mods: Rc<Moderators>,
     pub bans: Rc<Bans>,
 }
 
 impl Component for LivePage {
-    type Message = ();
-    type Properties = Self;
+    type Message = Msg;
+    type Properties = Props;
 
-    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         #[cfg(debug_assertions)]
         ConsoleService::info("Live Page Created");
 
-        props
+        Self {
+            bans: props.bans.clone(),
+
+            props,
+
+            event_cb: link.callback(Msg::Chat),
+            unban_cb: link.callback(Msg::Unban),
+            bans_cb: link.callback(Msg::Bans),
+
+            senders: Rc::from(VecDeque::with_capacity(RECENT_SENDERS)),
+            names: Rc::from(Vec::new()),
+            session_bans: Rc::from(Vec::new()),
+            unbanned: Rc::from(HashSet::new()),
+
+            modes: ChatModes::default(),
+            moderator: false,
+        }
     }
 
-    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
-        false
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Chat(event) => self.on_chat_event(event),
+            Msg::Unban(peer) => self.on_unban(peer),
+            Msg::Bans(bans) => {
+                self.bans = bans;
+
+                true
+            }
+        }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if !Rc::ptr_eq(&props.live, &self.live)
-            || !Rc::ptr_eq(&props.bans, &self.bans)
-            || !Rc::ptr_eq(&props.mods, &self.mods)
-            || props.peer_id != self.peer_id
+        if !Rc::ptr_eq(&props.live, &self.props.live)
+            || !Rc::ptr_eq(&props.limits, &self.props.limits)
+            || !Rc::ptr_eq(&props.emotes, &self.props.emotes)
+            || !Rc::ptr_eq(&props.friends, &self.props.friends)
+            || !Rc::ptr_eq(&props.bans, &self.props.bans)
+            || !Rc::ptr_eq(&props.mods, &self.props.mods)
+            || props.beacon.as_ref().map(Rc::as_ptr) != self.props.beacon.as_ref().map(Rc::as_ptr)
+            || props.peer_id != self.props.peer_id
+            || props.error != self.props.error
+            || props.read_only != self.props.read_only
         {
-            *self = props;
+            if !Rc::ptr_eq(&props.bans, &self.props.bans) {
+                self.bans = props.bans.clone();
+            }
+
+            self.props = props;
 
             #[cfg(debug_assertions)]
             ConsoleService::info("Live Page Changed");
@@ -68,21 +149,30 @@ impl Component for LivePage {
                 <Navbar />
                 <ybc::Section>
                 {
-                    if self.peer_id.is_none() {
-                        html! { <IPFSConnectionError /> }
-                    } else {
+                    if self.props.read_only {
+                        html! {
+                        <>
+                            <ReadOnlyNotice />
+                            <IPFSConnectionError error=Some(IpfsError::ReadOnly) />
+                        </>
+                        }
+                    } else if let Some(peer_id) = self.props.peer_id {
                         html! {
                         <ybc::Columns>
                             <ybc::Column>
                                 <ybc::Box>
-                                    <VideoPlayer ipfs=self.ipfs.clone() beacon_or_metadata=Either::Left(self.live.clone()) />
+                                    <VideoPlayer ipfs=self.props.ipfs.clone() beacon_or_metadata=Either::Left(self.props.live.clone()) />
                                 </ybc::Box>
+                                { self.render_moderation(peer_id) }
                             </ybc::Column>
                             <ybc::Column classes=classes!("is-one-fifth") >
-                                <ChatWindow ipfs=self.ipfs.clone() web3=self.web3.clone() storage=self.storage.clone() live=self.live.clone() bans=self.bans.clone() mods=self.mods.clone() />
+                                <ChatWindow ipfs=self.props.ipfs.clone() web3=self.props.web3.clone() storage=self.props.storage.clone() peer_id=peer_id live=self.props.live.clone() limits=self.props.limits.clone() emotes=self.props.emotes.clone() modes=self.modes names=self.names.clone()
+                                    friends=self.props.friends.clone() bans=self.bans.clone() mods=self.props.mods.clone() unbanned=self.unbanned.clone() event_cb=self.event_cb.clone() />
                             </ybc::Column>
                         </ybc::Columns>
                         }
+                    } else {
+                        html! { <IPFSConnectionError error=self.props.error.clone() /> }
                     }
                 }
                 </ybc::Section>
@@ -90,3 +180,63 @@ impl Component for LivePage {
         }
     }
 }
+
+impl LivePage {
+    /// Moderation tools, for the streamer and moderators only.
+    fn render_moderation(&self, peer_id: PeerId) -> Html {
+        let streamer = peer_id == self.props.live.peer_id;
+
+        if !streamer && !self.moderator {
+            return html! {};
+        }
+
+        html! {
+            <ModerationPanel ipfs=self.props.ipfs.clone() web3=self.props.web3.clone() storage=self.props.storage.clone() beacon=self.props.beacon.clone()
+                streamer=streamer moderator=self.moderator live=self.props.live.clone() modes=self.modes senders=self.senders.clone() session_bans=self.session_bans.clone() bans=self.bans.clone()
+                unban_cb=self.unban_cb.clone() bans_cb=self.bans_cb.clone() />
+        }
+    }
+
+    fn on_chat_event(&mut self, event: ChatEvent) -> bool {
+        match event {
+            ChatEvent::Sender(sender) => {
+                let senders = Rc::make_mut(&mut self.senders);
+
+                senders.retain(|probe| probe.peer != sender.peer);
+                senders.push_front(sender);
+                senders.truncate(RECENT_SENDERS);
+            }
+            ChatEvent::Ban(sender) => {
+                if self.unbanned.contains(&sender.peer) {
+                    Rc::make_mut(&mut self.unbanned).remove(&sender.peer);
+                }
+
+                let session_bans = Rc::make_mut(&mut self.session_bans);
+
+                if session_bans.iter().any(|probe| probe.peer == sender.peer) {
+                    return false;
+                }
+
+                session_bans.push(sender);
+            }
+            ChatEvent::Modes(modes) => {
+                if modes == self.modes {
+                    return false;
+                }
+
+                self.modes = modes;
+            }
+            ChatEvent::Authorised(moderator) => self.moderator = moderator,
+            ChatEvent::Names(names) => self.names = names,
+        }
+
+        true
+    }
+
+    fn on_unban(&mut self, peer: PeerId) -> bool {
+        Rc::make_mut(&mut self.session_bans).retain(|probe| probe.peer != peer);
+        Rc::make_mut(&mut self.unbanned).insert(peer);
+
+        true
+    }
+}
@@ -1,3 +1,4 @@
+mod compose;
 mod content;
 mod feed;
 mod home;
@@ -5,6 +6,7 @@ mod live;
 mod settings;
 mod start;
 
+pub use compose::Compose;
 pub use content::Content;
 pub use feed::ContentFeed;
 pub use home::Home;
@@ -1,13 +1,27 @@
-use crate::{components::Navbar, utils::LocalStorage};
+use std::{collections::HashMap, convert::TryFrom};
+
+use crate::{
+    components::{Navbar, DEFAULT_CHAT_HISTORY},
+    utils::{
+        gateway_url, test_gateway, CancelToken, IpfsError, IpfsService, LocalStorage, Seeded,
+        DEFAULT_GATEWAYS, DEFAULT_MAX_REQUESTS,
+    },
+};
+
+use wasm_bindgen_futures::spawn_local;
+
+use web_sys::{Notification, NotificationPermission};
 
 use yew::{
     prelude::{classes, html, Component, ComponentLink, Html, Properties, ShouldRender},
     services::ConsoleService,
-    Callback, ChangeData, MouseEvent,
+    Callback, ChangeData, InputData, MouseEvent,
 };
 
 use linked_data::PeerId;
 
+use cid::Cid;
+
 /* #[derive(PartialEq)]
 pub enum NodeType {
     Brave,
@@ -20,10 +34,19 @@ pub enum OsType {
     Windows,
 }
 
+pub enum GatewayStatus {
+    Testing,
+    Online,
+    Offline(IpfsError),
+}
+
 /// Page with app settings and options.
 pub struct Settings {
+    link: ComponentLink<Self>,
+    ipfs: IpfsService,
     storage: LocalStorage,
     peer_id: Option<PeerId>,
+    error: Option<IpfsError>,
     origin: String,
 
     address: String,
@@ -33,18 +56,64 @@ pub struct Settings {
     os_type: OsType,
     window_cb: Callback<MouseEvent>,
     unix_cb: Callback<MouseEvent>,
+
+    /// Public gateways in order of preference
+    gateways: Vec<String>,
+    gateway_status: HashMap<String, GatewayStatus>,
+    new_gateway: String,
+    new_gateway_error: bool,
+    gateway_input_cb: Callback<InputData>,
+    gateway_add_cb: Callback<MouseEvent>,
+
+    persistent_cache: bool,
+    persistent_cache_cb: Callback<MouseEvent>,
+
+    max_requests: usize,
+    max_requests_cb: Callback<ChangeData>,
+
+    chat_history: usize,
+    chat_history_cb: Callback<ChangeData>,
+
+    chat_notify: bool,
+    chat_notify_cb: Callback<MouseEvent>,
+
+    token: CancelToken,
+
+    /// Content pinned from content pages
+    seeded: Vec<Seeded>,
+    seeded_sizes: HashMap<String, u64>,
+    unpinning: Option<String>,
 }
 
 pub enum Msg {
     //NodeType(ChangeData),
     Addrs(ChangeData),
     OsType(OsType),
+    GatewayInput(String),
+    GatewayAdd,
+    GatewayRemove(usize),
+    GatewayUp(usize),
+    GatewayDown(usize),
+    GatewayTest(usize),
+    GatewayTested((String, Result<(), IpfsError>)),
+    PersistentCache,
+    MaxRequests(ChangeData),
+    ChatHistory(ChangeData),
+    ChatNotify,
+    Pins(Result<Vec<Cid>, IpfsError>),
+    SeededSize((String, Result<u64, IpfsError>)),
+    Unpin(usize),
+    Unpinned((String, Result<(), IpfsError>)),
+    #[cfg(debug_assertions)]
+    CacheStats,
 }
 
 #[derive(Properties, Clone)]
 pub struct Props {
+    pub ipfs: IpfsService,
     pub storage: LocalStorage,
     pub peer_id: Option<PeerId>,
+    pub error: Option<IpfsError>,
 }
 
 impl Component for Settings {
@@ -52,7 +121,12 @@ impl Component for Settings {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let Props { storage, peer_id } = props;
+        let Props {
+            ipfs,
+            storage,
+            peer_id,
+            error,
+        } = props;
 
         let address = match storage.get_local_ipfs_addrs() {
             Some(addrs) => addrs,
@@ -76,9 +150,37 @@ impl Component for Settings {
             }
         }
 
-        Self {
+        let gateways = match storage.get_gateways() {
+            Some(gateways) => gateways,
+            None => DEFAULT_GATEWAYS.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let seeded = storage.get_seeded();
+
+        let mut comp = Self {
+            gateway_input_cb: link.callback(|e: InputData| Msg::GatewayInput(e.value)),
+            gateway_add_cb: link.callback(|_| Msg::GatewayAdd),
+            gateway_status: HashMap::with_capacity(gateways.len()),
+            gateways,
+            new_gateway: String::default(),
+            new_gateway_error: false,
+
+            persistent_cache: storage.get_persistent_cache(),
+            persistent_cache_cb: link.callback(|_| Msg::PersistentCache),
+
+            max_requests: storage.get_max_requests().unwrap_or(DEFAULT_MAX_REQUESTS),
+            max_requests_cb: link.callback(Msg::MaxRequests),
+
+            chat_history: storage.get_chat_history().unwrap_or(DEFAULT_CHAT_HISTORY),
+            chat_history_cb: link.callback(Msg::ChatHistory),
+
+            chat_notify: storage.get_chat_notify(),
+            chat_notify_cb: link.callback(|_| Msg::ChatNotify),
+
+            ipfs,
             storage,
             peer_id,
+            error,
             origin,
 
             address,
@@ -88,7 +190,18 @@ impl Component for Settings {
             window_cb: link.callback(|__event: MouseEvent| Msg::OsType(OsType::Windows)),
             unix_cb: link.callback(|_event: MouseEvent| Msg::OsType(OsType::Unix)),
             os_type: OsType::Unix,
-        }
+            link,
+
+            token: CancelToken::new(),
+
+            seeded,
+            seeded_sizes: HashMap::default(),
+            unpinning: None,
+        };
+
+        comp.get_pins();
+
+        comp
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
@@ -104,12 +217,84 @@ impl Component for Settings {
 
                 changed
             }
+            Msg::GatewayInput(value) => {
+                self.new_gateway = value;
+                self.new_gateway_error = false;
+
+                false
+            }
+            Msg::GatewayAdd => self.on_gateway_add(),
+            Msg::GatewayRemove(index) => {
+                self.gateways.remove(index);
+                self.storage.set_gateways(&self.gateways);
+
+                true
+            }
+            Msg::GatewayUp(index) => {
+                if index == 0 {
+                    return false;
+                }
+
+                self.gateways.swap(index, index - 1);
+                self.storage.set_gateways(&self.gateways);
+
+                true
+            }
+            Msg::GatewayDown(index) => {
+                if index + 1 >= self.gateways.len() {
+                    return false;
+                }
+
+                self.gateways.swap(index, index + 1);
+                self.storage.set_gateways(&self.gateways);
+
+                true
+            }
+            Msg::GatewayTest(index) => self.on_gateway_test(index),
+            Msg::GatewayTested((gateway, result)) => {
+                let status = match result {
+                    Ok(()) => GatewayStatus::Online,
+                    Err(e) => GatewayStatus::Offline(e),
+                };
+
+                self.gateway_status.insert(gateway, status);
+
+                true
+            }
+            Msg::PersistentCache => {
+                self.persistent_cache = !self.persistent_cache;
+                self.storage.set_persistent_cache(self.persistent_cache);
+
+                true
+            }
+            Msg::MaxRequests(msg) => self.on_max_requests(msg),
+            Msg::ChatHistory(msg) => self.on_chat_history(msg),
+            Msg::ChatNotify => self.on_chat_notify(),
+            Msg::Pins(result) => self.on_pins(result),
+            Msg::SeededSize((cid, result)) => match result {
+                Ok(size) => {
+                    self.seeded_sizes.insert(cid, size);
+
+                    true
+                }
+                Err(IpfsError::Aborted) => false,
+                Err(e) => {
+                    ConsoleService::error(&format!("{:?}", e));
+
+                    false
+                }
+            },
+            Msg::Unpin(index) => self.on_unpin(index),
+            Msg::Unpinned((cid, result)) => self.on_unpinned(cid, result),
+            #[cfg(debug_assertions)]
+            Msg::CacheStats => true,
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if props.peer_id != self.peer_id {
+        if props.peer_id != self.peer_id || props.error != self.error {
             self.peer_id = props.peer_id;
+            self.error = props.error;
 
             return true;
         }
@@ -151,11 +336,41 @@ impl Component for Settings {
                             </div>
                             <p class="help"> { "Refresh to apply changes." } </p>
                         </div>
+                        { self.render_gateways() }
+                        { self.render_cache() }
+                        { self.render_seeded() }
+                        <div class="field">
+                            <label class="label"> { "Concurrent Requests" } </label>
+                            <div class="control">
+                                <input value=self.max_requests.to_string() onchange=self.max_requests_cb.clone() class="input" type="number" min="1" />
+                            </div>
+                            <p class="help"> { "Maximum number of simultaneous requests to IPFS, video playback always goes first. Refresh to apply changes." } </p>
+                        </div>
+                        <div class="field">
+                            <label class="label"> { "Chat History" } </label>
+                            <div class="control">
+                                <input value=self.chat_history.to_string() onchange=self.chat_history_cb.clone() class="input" type="number" min="1" />
+                            </div>
+                            <p class="help"> { "Number of live chat messages kept, older ones are dropped." } </p>
+                        </div>
+                        <div class="field">
+                            <label class="label"> { "Chat Mentions" } </label>
+                            <div class="control">
+                                <label class="checkbox">
+                                    <input type="checkbox" checked=self.chat_notify onclick=self.chat_notify_cb.clone() />
+                                    { " Desktop notification when mentioned while the tab is in the background" }
+                                </label>
+                            </div>
+                        </div>
                     </ybc::Container>
                 </ybc::Section>
             </>
         }
     }
+
+    fn destroy(&mut self) {
+        self.token.cancel();
+    }
 }
 
 impl Settings {
@@ -199,6 +414,12 @@ impl Settings {
                     <span class="icon is-large has-text-danger"><i class="fas fa-exclamation-triangle fa-3x"></i></span>
                     <span class="title"> { "Cannot connect to IPFS" } </span>
                 </span>
+                {
+                    match &self.error {
+                        Some(error) => html! { <p class="help is-danger"> { error.to_string() } </p> },
+                        None => html! {},
+                    }
+                }
                 </ybc::Block>
                 <ybc::Block>
                 <h2 class="subtitle">
@@ -253,6 +474,333 @@ impl Settings {
         }
     }
 
+    fn render_gateways(&self) -> Html {
+        let last = self.gateways.len().saturating_sub(1);
+
+        html! {
+            <div class="field">
+                <label class="label"> { "IPFS Gateways" } </label>
+                {
+                    self.gateways.iter().enumerate().map(|(i, gateway)| {
+                        let status = match self.gateway_status.get(gateway) {
+                            Some(GatewayStatus::Testing) => html! { <p class="help"> { "Testing..." } </p> },
+                            Some(GatewayStatus::Online) => html! { <p class="help is-success"> { "Online" } </p> },
+                            Some(GatewayStatus::Offline(e)) => html! { <p class="help is-danger"> { e.to_string() } </p> },
+                            None => html! {},
+                        };
+
+                        html! {
+                        <>
+                            <div class="field has-addons">
+                                <div class="control is-expanded">
+                                    <input value=gateway.clone() class="input" type="text" readonly=true />
+                                </div>
+                                <div class="control">
+                                    <button class="button" onclick=self.link.callback(move |_| Msg::GatewayTest(i)) >
+                                        <span class="icon"><i class="fas fa-vial"></i></span>
+                                    </button>
+                                </div>
+                                <div class="control">
+                                    <button class="button" disabled={i == 0} onclick=self.link.callback(move |_| Msg::GatewayUp(i)) >
+                                        <span class="icon"><i class="fas fa-arrow-up"></i></span>
+                                    </button>
+                                </div>
+                                <div class="control">
+                                    <button class="button" disabled={i == last} onclick=self.link.callback(move |_| Msg::GatewayDown(i)) >
+                                        <span class="icon"><i class="fas fa-arrow-down"></i></span>
+                                    </button>
+                                </div>
+                                <div class="control">
+                                    <button class="button is-danger" onclick=self.link.callback(move |_| Msg::GatewayRemove(i)) >
+                                        <span class="icon"><i class="fas fa-trash"></i></span>
+                                    </button>
+                                </div>
+                            </div>
+                            { status }
+                        </>
+                        }
+                    }).collect::<Html>()
+                }
+                <div class="field has-addons">
+                    <div class="control is-expanded">
+                        <input value=self.new_gateway.clone() oninput=self.gateway_input_cb.clone() class=classes!("input", self.new_gateway_error.then(|| "is-danger")) type="text" placeholder="https://ipfs.io/" />
+                    </div>
+                    <div class="control">
+                        <button class="button is-primary" onclick=self.gateway_add_cb.clone() >
+                            { "Add" }
+                        </button>
+                    </div>
+                </div>
+                <p class="help"> { "Used in order, in read-only mode, when no IPFS node can be reached. Refresh to apply changes." } </p>
+            </div>
+        }
+    }
+
+    fn render_cache(&self) -> Html {
+        #[cfg(debug_assertions)]
+        let stats = {
+            let stats = self.ipfs.cache_stats();
+
+            html! {
+                <p class="help">
+                    { format!("Hits {} (persistent {}) Misses {} Entries {} Size {} KiB ", stats.hits, stats.persistent_hits, stats.misses, stats.entries, stats.size / 1024) }
+                    <a onclick=self.link.callback(|_| Msg::CacheStats) > { "Refresh" } </a>
+                </p>
+            }
+        };
+
+        #[cfg(not(debug_assertions))]
+        let stats = html! {};
+
+        html! {
+            <div class="field">
+                <label class="label"> { "Block Cache" } </label>
+                <div class="control">
+                    <label class="checkbox">
+                        <input type="checkbox" checked=self.persistent_cache onclick=self.persistent_cache_cb.clone() />
+                        { " Keep content between sessions (IndexedDB)" }
+                    </label>
+                </div>
+                <p class="help"> { "Refresh to apply changes." } </p>
+                { stats }
+            </div>
+        }
+    }
+
+    fn render_seeded(&self) -> Html {
+        if self.ipfs.is_read_only() {
+            return html! {};
+        }
+
+        html! {
+            <div class="field">
+                <label class="label"> { "Seeded Content" } </label>
+                {
+                    self.seeded.iter().enumerate().map(|(i, item)| {
+                        let size = match self.seeded_sizes.get(&item.cid) {
+                            Some(size) if *size < 1024 * 1024 => format!("{} KiB", size / 1024),
+                            Some(size) => format!("{} MiB", size / (1024 * 1024)),
+                            None => "...".to_owned(),
+                        };
+
+                        let unpinning = self.unpinning.as_ref() == Some(&item.cid);
+
+                        html! {
+                            <div class="field has-addons">
+                                <div class="control is-expanded">
+                                    <input value=item.title.clone() title=item.cid.clone() class="input" type="text" readonly=true />
+                                </div>
+                                <div class="control">
+                                    <input value=size class="input" type="text" readonly=true />
+                                </div>
+                                <div class="control">
+                                    <button class=classes!("button", "is-danger", unpinning.then(|| "is-loading")) disabled=self.unpinning.is_some() onclick=self.link.callback(move |_| Msg::Unpin(i)) >
+                                        <span class="icon"><i class="fas fa-trash"></i></span>
+                                    </button>
+                                </div>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+                <p class="help"> { "Content pinned on your node from content pages. Unpinned content will be removed on the next garbage collection." } </p>
+            </div>
+        }
+    }
+
+    /// Forget seeded content that was unpinned outside this app then get sizes.
+    fn get_pins(&self) {
+        if self.seeded.is_empty() || self.ipfs.is_read_only() {
+            return;
+        }
+
+        spawn_local({
+            let cb = self.link.callback(Msg::Pins);
+            let ipfs = self.ipfs.with_token(&self.token);
+
+            async move { cb.emit(ipfs.pin_ls().await) }
+        });
+    }
+
+    fn on_pins(&mut self, result: Result<Vec<Cid>, IpfsError>) -> bool {
+        let pins = match result {
+            Ok(pins) => pins,
+            Err(IpfsError::Aborted) => return false,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return false;
+            }
+        };
+
+        let count = self.seeded.len();
+
+        self.seeded
+            .retain(|item| match Cid::try_from(item.cid.as_str()) {
+                Ok(cid) => pins.contains(&cid),
+                Err(_) => false,
+            });
+
+        if self.seeded.len() != count {
+            self.storage.set_seeded(&self.seeded);
+        }
+
+        for item in self.seeded.iter() {
+            spawn_local({
+                let cb = self.link.callback(Msg::SeededSize);
+                let ipfs = self.ipfs.with_token(&self.token);
+                let cid = item.cid.clone();
+
+                async move {
+                    let result = ipfs.stat(cid.clone()).await;
+
+                    cb.emit((cid, result))
+                }
+            });
+        }
+
+        true
+    }
+
+    fn on_unpin(&mut self, index: usize) -> bool {
+        if self.unpinning.is_some() {
+            return false;
+        }
+
+        let item = match self.seeded.get(index) {
+            Some(item) => item,
+            None => return false,
+        };
+
+        let cid = match Cid::try_from(item.cid.as_str()) {
+            Ok(cid) => cid,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return false;
+            }
+        };
+
+        spawn_local({
+            let cb = self.link.callback(Msg::Unpinned);
+            let ipfs = self.ipfs.with_token(&self.token);
+            let key = item.cid.clone();
+
+            async move {
+                let result = ipfs.pin_rm(cid, true).await;
+
+                cb.emit((key, result))
+            }
+        });
+
+        self.unpinning = Some(item.cid.clone());
+
+        true
+    }
+
+    fn on_unpinned(&mut self, cid: String, result: Result<(), IpfsError>) -> bool {
+        self.unpinning = None;
+
+        match result {
+            Ok(()) => {}
+            Err(IpfsError::Aborted) => return false,
+            Err(e) => {
+                ConsoleService::error(&format!("{:?}", e));
+                return true;
+            }
+        }
+
+        self.seeded.retain(|item| item.cid != cid);
+        self.seeded_sizes.remove(&cid);
+        self.storage.set_seeded(&self.seeded);
+
+        true
+    }
+
+    fn on_gateway_add(&mut self) -> bool {
+        let url = match gateway_url(&self.new_gateway) {
+            Some(url) => url.to_string(),
+            None => {
+                self.new_gateway_error = true;
+                return true;
+            }
+        };
+
+        if !self.gateways.contains(&url) {
+            self.gateways.push(url);
+            self.storage.set_gateways(&self.gateways);
+        }
+
+        self.new_gateway.clear();
+
+        true
+    }
+
+    fn on_gateway_test(&mut self, index: usize) -> bool {
+        let gateway = match self.gateways.get(index) {
+            Some(gateway) => gateway.clone(),
+            None => return false,
+        };
+
+        let url = match gateway_url(&gateway) {
+            Some(url) => url,
+            None => return false,
+        };
+
+        spawn_local({
+            let cb = self.link.callback(Msg::GatewayTested);
+            let gateway = gateway.clone();
+
+            async move { cb.emit((gateway, test_gateway(url).await)) }
+        });
+
+        self.gateway_status.insert(gateway, GatewayStatus::Testing);
+
+        true
+    }
+
+    fn on_max_requests(&mut self, msg: ChangeData) -> bool {
+        let value = match msg {
+            ChangeData::Value(value) => value,
+            _ => return false,
+        };
+
+        if let Ok(max) = value.parse::<usize>() {
+            if max > 0 {
+                self.max_requests = max;
+                self.storage.set_max_requests(max);
+            }
+        }
+
+        true
+    }
+
+    fn on_chat_history(&mut self, msg: ChangeData) -> bool {
+        let value = match msg {
+            ChangeData::Value(value) => value,
+            _ => return false,
+        };
+
+        if let Ok(length) = value.parse::<usize>() {
+            if length > 0 {
+                self.chat_history = length;
+                self.storage.set_chat_history(length);
+            }
+        }
+
+        true
+    }
+
+    fn on_chat_notify(&mut self) -> bool {
+        self.chat_notify = !self.chat_notify;
+        self.storage.set_chat_notify(self.chat_notify);
+
+        if self.chat_notify && Notification::permission() == NotificationPermission::Default {
+            if let Err(e) = Notification::request_permission() {
+                ConsoleService::error(&format!("{:?}", e));
+            }
+        }
+
+        true
+    }
+
     fn on_addrs(&mut self, msg: ChangeData) -> bool {
         let value = match msg {
             ChangeData::Value(value) => value,
@@ -6,12 +6,20 @@ use web_sys::Performance;
 /// [0.0 <= P <= 1.0]
 const MOVING_AVERAGE_P: f64 = 0.15;
 
+/// Chunks closer than this are measured together, in milliseconds.
+const MIN_SAMPLE_TIME: f64 = 100.0;
+
 #[derive(Clone)]
 pub struct ExponentialMovingAverage {
     performance: Performance,
 
-    download_time: f64,
+    /// Start of the current sample.
+    sample_time: f64,
+
+    /// Bytes received during the current sample.
+    sample_bytes: u64,
 
+    /// Bits per second.
     moving_average: f64,
 }
 
@@ -36,35 +44,39 @@ impl ExponentialMovingAverage {
         Self {
             performance,
 
-            download_time: 0.0,
+            sample_time: 0.0,
+            sample_bytes: 0,
             moving_average: 0.0,
         }
     }
 
     pub fn start_timer(&mut self) {
-        self.download_time = self.performance.now();
+        self.sample_time = self.performance.now();
+        self.sample_bytes = 0;
     }
 
-    /// Returns the newly calculated average if start_timer() was previously called
-    pub fn recalculate_average_speed(&mut self, bandwidth: f64) -> Option<f64> {
-        if self.download_time <= 0.0 {
-            return None;
+    /// Count downloaded bytes, the average is updated once enough time has passed.
+    pub fn add_bytes(&mut self, bytes: usize) {
+        if self.sample_time <= 0.0 {
+            return;
         }
 
-        let time = self.performance.now() - self.download_time;
+        self.sample_bytes += bytes as u64;
 
-        self.download_time = 0.0;
+        if self.performance.now() - self.sample_time >= MIN_SAMPLE_TIME {
+            self.add_sample();
+        }
+    }
 
-        #[cfg(debug_assertions)]
-        ConsoleService::info(&format!("Last Download {:.0}ms", time));
+    /// Returns the newly calculated average if start_timer() was previously called
+    pub fn recalculate_average_speed(&mut self) -> Option<f64> {
+        if self.sample_time <= 0.0 {
+            return None;
+        }
 
-        let new_bitrate = bandwidth / time * 1000.0;
+        self.add_sample();
 
-        if self.moving_average >= 0.0 {
-            self.moving_average += (new_bitrate - self.moving_average) * MOVING_AVERAGE_P;
-        } else {
-            self.moving_average = new_bitrate; // the first entry
-        }
+        self.sample_time = 0.0;
 
         #[cfg(debug_assertions)]
         ConsoleService::info(&format!(
@@ -74,4 +86,24 @@ impl ExponentialMovingAverage {
 
         Some(self.moving_average)
     }
+
+    fn add_sample(&mut self) {
+        let now = self.performance.now();
+        let time = now - self.sample_time;
+
+        if time <= 0.0 || self.sample_bytes == 0 {
+            return;
+        }
+
+        let new_bitrate = self.sample_bytes as f64 * 8.0 / time * 1000.0;
+
+        if self.moving_average > 0.0 {
+            self.moving_average += (new_bitrate - self.moving_average) * MOVING_AVERAGE_P;
+        } else {
+            self.moving_average = new_bitrate; // the first entry
+        }
+
+        self.sample_time = now;
+        self.sample_bytes = 0;
+    }
 }
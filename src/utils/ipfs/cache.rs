@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Default memory budget of the block cache in bytes.
+pub const DEFAULT_CACHE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Content-addressed least recently used cache, bounded by bytes.
+///
+/// Keys are immutable IPFS paths (CID and optional path) so entries never go stale.
+pub struct BlockCache {
+    capacity: usize,
+    size: usize,
+
+    tick: u64,
+
+    /// Maps path to last use and data
+    entries: HashMap<String, (u64, Vec<u8>)>,
+
+    /// Maps last use to path
+    order: BTreeMap<u64, String>,
+
+    #[cfg(debug_assertions)]
+    stats: CacheStats,
+}
+
+#[cfg(debug_assertions)]
+#[derive(Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub persistent_hits: u64,
+    pub entries: usize,
+    pub size: usize,
+}
+
+impl BlockCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+
+            tick: 0,
+
+            entries: HashMap::with_capacity(100),
+            order: BTreeMap::new(),
+
+            #[cfg(debug_assertions)]
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<Vec<u8>> {
+        let (last_use, data) = match self.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => {
+                #[cfg(debug_assertions)]
+                {
+                    self.stats.misses += 1;
+                }
+
+                return None;
+            }
+        };
+
+        self.tick += 1;
+
+        if let Some(key) = self.order.remove(&*last_use) {
+            self.order.insert(self.tick, key);
+        }
+
+        *last_use = self.tick;
+
+        #[cfg(debug_assertions)]
+        {
+            self.stats.hits += 1;
+        }
+
+        Some(data.clone())
+    }
+
+    pub fn insert(&mut self, key: String, data: Vec<u8>) {
+        if data.len() > self.capacity || self.entries.contains_key(&key) {
+            return;
+        }
+
+        self.size += data.len();
+
+        while self.size > self.capacity {
+            let oldest = match self.order.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+
+            if let Some(old_key) = self.order.remove(&oldest) {
+                if let Some((_, old_data)) = self.entries.remove(&old_key) {
+                    self.size -= old_data.len();
+                }
+            }
+        }
+
+        self.tick += 1;
+
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (self.tick, data));
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn persistent_hit(&mut self) {
+        self.stats.persistent_hits += 1;
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            size: self.size,
+            ..self.stats
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use futures_util::future::{AbortHandle, AbortRegistration};
+
+#[derive(Default)]
+struct Inner {
+    cancelled: Cell<bool>,
+    next_id: Cell<usize>,
+    handles: RefCell<HashMap<usize, AbortHandle>>,
+}
+
+/// Abort every outstanding request made with this token.
+///
+/// Components cancel their token when destroyed.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Rc<Inner>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.set(true);
+
+        for (_, handle) in self.inner.handles.borrow_mut().drain() {
+            handle.abort();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.get()
+    }
+
+    /// Track a request until the returned guard is dropped.
+    pub(super) fn register(&self) -> (Registration, AbortRegistration) {
+        let (handle, regis) = AbortHandle::new_pair();
+
+        if self.is_cancelled() {
+            handle.abort();
+        }
+
+        let id = self.inner.next_id.get();
+        self.inner.next_id.set(id + 1);
+
+        self.inner.handles.borrow_mut().insert(id, handle);
+
+        let registration = Registration {
+            inner: self.inner.clone(),
+            id,
+        };
+
+        (registration, regis)
+    }
+}
+
+pub(super) struct Registration {
+    inner: Rc<Inner>,
+    id: usize,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.inner.handles.borrow_mut().remove(&self.id);
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+
+use futures_util::future::Aborted;
+
+/// Message of daemon requests running past their timeout argument.
+const DAEMON_DEADLINE: &str = "context deadline exceeded";
+
+/// Errors returned by IpfsService.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpfsError {
+    /// No daemon answered at the API address.
+    ConnectionRefused,
+
+    /// The daemon is up but the browser blocked the request, most likely CORS.
+    Blocked,
+
+    /// The connection failed after the request was sent.
+    Transport(String),
+
+    /// The daemon answered with an error.
+    Daemon { code: u64, message: String },
+
+    /// The block, path or name could not be found.
+    NotFound(String),
+
+    /// The response could not be decoded.
+    Decode(String),
+
+    /// The request was aborted before completion.
+    Aborted,
+
+    /// Writing is impossible without a local daemon.
+    ReadOnly,
+
+    /// No answer before the deadline.
+    Timeout,
+}
+
+impl IpfsError {
+    /// Transient errors worth retrying.
+    ///
+    /// Daemon errors are deterministic, except when its own deadline ran out.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            IpfsError::Timeout | IpfsError::ConnectionRefused | IpfsError::Transport(_) => true,
+            IpfsError::Daemon { message, .. } => message.contains(DAEMON_DEADLINE),
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for IpfsError {}
+
+impl fmt::Display for IpfsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpfsError::ConnectionRefused => write!(f, "Cannot connect to the IPFS daemon"),
+            IpfsError::Blocked => write!(f, "Request blocked by the IPFS API CORS configuration"),
+            IpfsError::Transport(e) => write!(f, "Connection lost: {}", e),
+            IpfsError::Daemon { code, message } => {
+                write!(f, "IPFS daemon error {}: {}", code, message)
+            }
+            IpfsError::NotFound(what) => write!(f, "Not found: {}", what),
+            IpfsError::Decode(e) => write!(f, "Cannot decode response: {}", e),
+            IpfsError::Aborted => write!(f, "Request aborted"),
+            IpfsError::ReadOnly => write!(f, "Not available in read-only mode"),
+            IpfsError::Timeout => write!(f, "Request timed out"),
+        }
+    }
+}
+
+impl From<Aborted> for IpfsError {
+    fn from(_: Aborted) -> Self {
+        IpfsError::Aborted
+    }
+}
+
+impl From<std::io::Error> for IpfsError {
+    fn from(e: std::io::Error) -> Self {
+        IpfsError::Transport(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for IpfsError {
+    fn from(e: serde_json::Error) -> Self {
+        IpfsError::Decode(e.to_string())
+    }
+}
+
+impl From<cid::Error> for IpfsError {
+    fn from(e: cid::Error) -> Self {
+        IpfsError::Decode(e.to_string())
+    }
+}
+
+impl From<cid::multibase::Error> for IpfsError {
+    fn from(e: cid::multibase::Error) -> Self {
+        IpfsError::Decode(e.to_string())
+    }
+}
+
+impl From<cid::multihash::Error> for IpfsError {
+    fn from(e: cid::multihash::Error) -> Self {
+        IpfsError::Decode(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn daemon_errors_are_not_retried() {
+        let error = IpfsError::Daemon {
+            code: 0,
+            message: "no link named \"live\" under bafy".to_owned(),
+        };
+
+        assert!(!error.is_retryable());
+    }
+
+    #[wasm_bindgen_test]
+    fn daemon_deadline_is_retried() {
+        let error = IpfsError::Daemon {
+            code: 0,
+            message: "context deadline exceeded".to_owned(),
+        };
+
+        assert!(error.is_retryable());
+    }
+
+    #[wasm_bindgen_test]
+    fn transient_errors_are_retried() {
+        assert!(IpfsError::Timeout.is_retryable());
+        assert!(IpfsError::ConnectionRefused.is_retryable());
+        assert!(IpfsError::Transport("reset".to_owned()).is_retryable());
+
+        assert!(!IpfsError::Blocked.is_retryable());
+        assert!(!IpfsError::ReadOnly.is_retryable());
+        assert!(!IpfsError::Aborted.is_retryable());
+    }
+}
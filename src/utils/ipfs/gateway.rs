@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use crate::utils::ipfs::{http::HttpBackend, AddOptions, IpfsBackend, IpfsError};
+
+use futures_util::{
+    future::{AbortRegistration, LocalBoxFuture},
+    join, TryStreamExt,
+};
+
+use yew::Callback;
+
+use cid::Cid;
+
+use linked_data::PeerId;
+
+use reqwest::{Client, RequestBuilder, Response, Url};
+
+/// Public gateways used when no local IPFS daemon can be reached.
+pub const DEFAULT_GATEWAYS: [&str; 2] = ["https://ipfs.io/", "https://dweb.link/"];
+
+/// Identity CID of empty data, gateways can answer without searching the network.
+const PROBE_CID: &str = "bafkqaaa";
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+/// Read-only IPFS node made of public HTTP gateways, tried in order.
+pub struct GatewayBackend {
+    client: Client,
+    gateways: Vec<(Url, HttpBackend)>,
+}
+
+impl GatewayBackend {
+    pub fn new(gateways: Vec<Url>) -> Self {
+        let client = Client::new();
+
+        let gateways = gateways
+            .into_iter()
+            .filter_map(|url| {
+                let api = url.join("api/v0/").ok()?;
+
+                Some((url, HttpBackend::new(api)))
+            })
+            .collect();
+
+        Self { client, gateways }
+    }
+
+    /// Path-style gateway request, /ipfs/<cid>/<path>
+    async fn cat(&self, path: String) -> Result<Vec<u8>> {
+        let response = self.path_request(&path, false).await?;
+
+        let bytes = response.bytes().await?;
+
+        Ok(bytes.to_vec())
+    }
+
+    async fn cat_chunks(&self, path: String, cb: Callback<Vec<u8>>) -> Result<()> {
+        let mut stream = self.path_request(&path, false).await?.bytes_stream();
+
+        while let Some(chunk) = stream.try_next().await? {
+            cb.emit(chunk.to_vec());
+        }
+
+        Ok(())
+    }
+
+    /// Size from the content length of a HEAD request.
+    async fn size(&self, path: String) -> Result<u64> {
+        let response = self.path_request(&path, true).await?;
+
+        match response.content_length() {
+            Some(size) => Ok(size),
+            None => Err(IpfsError::NotFound(path)),
+        }
+    }
+
+    /// Send the request to each gateway until one answers.
+    async fn path_request(&self, path: &str, head: bool) -> Result<Response> {
+        let path = format!("ipfs/{}", path.trim_start_matches("/ipfs/"));
+
+        let mut error = IpfsError::ConnectionRefused;
+
+        for (gateway, _) in self.gateways.iter() {
+            let url = match gateway.join(&path) {
+                Ok(url) => url,
+                Err(e) => return Err(IpfsError::Decode(e.to_string())),
+            };
+
+            let request = if head {
+                self.client.head(url.clone())
+            } else {
+                self.client.get(url.clone())
+            };
+
+            match path_send(request, &url).await {
+                Ok(response) => return Ok(response),
+                Err(e) => error = e,
+            }
+        }
+
+        Err(error)
+    }
+
+    async fn double_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (audio_result, video_result) = join!(self.cat(audio_path), self.cat(video_path));
+
+        Ok((audio_result?, video_result?))
+    }
+
+    async fn get(&self, path: String, timeout: Duration) -> Result<Vec<u8>> {
+        let mut error = IpfsError::ConnectionRefused;
+
+        for (_, api) in self.gateways.iter() {
+            match api.dag_get(path.clone(), timeout).await {
+                Ok(data) => return Ok(data),
+                Err(e) => error = e,
+            }
+        }
+
+        Err(error)
+    }
+
+    async fn resolve(&self, ipns: Cid, timeout: Duration) -> Result<Cid> {
+        let mut error = IpfsError::ConnectionRefused;
+
+        for (_, api) in self.gateways.iter() {
+            match api.name_resolve(ipns, timeout).await {
+                Ok(cid) => return Ok(cid),
+                Err(e) => error = e,
+            }
+        }
+
+        Err(error)
+    }
+}
+
+impl IpfsBackend for GatewayBackend {
+    fn cid_cat(&self, cid: Cid, _timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(self.cat(cid.to_string()))
+    }
+
+    fn double_path_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<(Vec<u8>, Vec<u8>)>> {
+        Box::pin(self.double_cat(audio_path, video_path))
+    }
+
+    fn cat_stream(
+        &self,
+        path: String,
+        cb: Callback<Vec<u8>>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.cat_chunks(path, cb))
+    }
+
+    fn stat(&self, path: String, _timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(self.size(path))
+    }
+
+    fn dag_put(&self, _data: String, _timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn dag_get(&self, path: String, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(self.get(path, timeout))
+    }
+
+    fn dag_stat(&self, _cid: Cid, _timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn name_resolve(&self, ipns: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(self.resolve(ipns, timeout))
+    }
+
+    fn name_publish(
+        &self,
+        _cid: Cid,
+        _key: String,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn add(
+        &self,
+        _data: Vec<u8>,
+        _options: AddOptions,
+        _cb: Callback<u64>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn pin_add(
+        &self,
+        _cid: Cid,
+        _recursive: bool,
+        _cb: Callback<u64>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn pin_rm(
+        &self,
+        _cid: Cid,
+        _recursive: bool,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn pin_ls(&self, _timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<Cid>>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn ipfs_node_id(&self) -> LocalBoxFuture<'_, Result<PeerId>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn pubsub_pub(&self, _topic: String, _msg: String) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+
+    fn pubsub_sub(
+        &self,
+        _topic: String,
+        _open_cb: Callback<()>,
+        _cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        _regis: AbortRegistration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async { Err(IpfsError::ReadOnly) })
+    }
+}
+
+/// Parse a gateway address, the trailing slash is added if missing.
+pub fn gateway_url(addrs: &str) -> Option<Url> {
+    let mut url = Url::parse(addrs).ok()?;
+
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+
+    Some(url)
+}
+
+/// Check that a gateway answer path-style requests.
+pub async fn test_gateway(gateway: Url) -> Result<()> {
+    let url = match gateway.join(&format!("ipfs/{}", PROBE_CID)) {
+        Ok(url) => url,
+        Err(e) => return Err(IpfsError::Decode(e.to_string())),
+    };
+
+    path_send(Client::new().get(url.clone()), &url).await?;
+
+    Ok(())
+}
+
+async fn path_send(request: RequestBuilder, url: &Url) -> Result<Response> {
+    let response = match request.send().await {
+        Ok(res) => res,
+        Err(_) => return Err(IpfsError::ConnectionRefused),
+    };
+
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let bytes = response.bytes().await?;
+
+    if status.as_u16() == 404 {
+        return Err(IpfsError::NotFound(url.path().to_owned()));
+    }
+
+    Err(IpfsError::Daemon {
+        code: status.as_u16() as u64,
+        message: String::from_utf8_lossy(&bytes).into_owned(),
+    })
+}
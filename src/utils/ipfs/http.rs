@@ -0,0 +1,806 @@
+use std::{cell::Cell, collections::HashMap, convert::TryFrom, time::Duration};
+
+use crate::utils::ipfs::{AddOptions, IpfsBackend, IpfsError};
+
+use futures_util::{
+    future::{AbortRegistration, Abortable, LocalBoxFuture},
+    join, AsyncBufReadExt, TryStreamExt,
+};
+
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
+
+#[cfg(debug_assertions)]
+use yew::services::ConsoleService;
+
+use yew::Callback;
+
+use cid::{
+    multibase::{self, Base},
+    multihash::MultihashGeneric,
+    Cid,
+};
+
+use linked_data::{peer_id_from_str, PeerId};
+
+use reqwest::{
+    multipart::{Form, Part},
+    Client, RequestBuilder, Response, Url,
+};
+
+pub const DEFAULT_URI: &str = "http://127.0.0.1:5001/api/v0/";
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+/// First Kubo version expecting multibase encoded pubsub topics.
+const MULTIBASE_PUBSUB_VERSION: (u32, u32) = (0, 11);
+
+/// Pubsub encoding spoken by the daemon.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PubsubDialect {
+    /// Raw topics, Base64Pad encoded sender and data.
+    Legacy,
+
+    /// Multibase encoded topics and data, sender as peer ID string.
+    Multibase,
+}
+
+/// IPFS node reached through the daemon's HTTP API.
+pub struct HttpBackend {
+    client: Client,
+    base_url: Url,
+
+    /// Detected once from the daemon version.
+    dialect: Cell<Option<PubsubDialect>>,
+}
+
+impl HttpBackend {
+    pub fn new(base_url: Url) -> Self {
+        let client = Client::new();
+
+        Self {
+            client,
+            base_url,
+            dialect: Cell::new(None),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url> {
+        match self.base_url.join(path) {
+            Ok(url) => Ok(url),
+            Err(e) => Err(IpfsError::Decode(e.to_string())),
+        }
+    }
+
+    /// Send the request then check the response for daemon errors.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = match request.send().await {
+            Ok(res) => res,
+            Err(_) => return Err(self.diagnose().await),
+        };
+
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let bytes = response.bytes().await?;
+
+        match serde_json::from_slice::<IPFSError>(&bytes) {
+            Ok(ipfs_error) => Err(ipfs_error.into()),
+            Err(_) => Err(IpfsError::Daemon {
+                code: status.as_u16() as u64,
+                message: String::from_utf8_lossy(&bytes).into_owned(),
+            }),
+        }
+    }
+
+    /// Browsers hide why a request failed, probe the API without CORS to find out.
+    async fn diagnose(&self) -> IpfsError {
+        let probe = self
+            .client
+            .post(self.base_url.clone())
+            .fetch_mode_no_cors()
+            .send()
+            .await;
+
+        // An opaque response means something is listening.
+        match probe {
+            Ok(_) => IpfsError::Blocked,
+            Err(_) => IpfsError::ConnectionRefused,
+        }
+    }
+
+    async fn json<T>(&self, request: RequestBuilder) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let bytes = self.send(request).await?.bytes().await?;
+
+        let res = serde_json::from_slice::<T>(&bytes)?;
+
+        Ok(res)
+    }
+
+    async fn cat(&self, path: String, timeout: Duration) -> Result<Vec<u8>> {
+        let url = self.endpoint("cat")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &path), ("timeout", &duration_arg(timeout))]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        Ok(bytes.to_vec())
+    }
+
+    async fn cat_chunks(
+        &self,
+        path: String,
+        cb: Callback<Vec<u8>>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let url = self.endpoint("cat")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &path), ("timeout", &duration_arg(timeout))]);
+
+        let mut stream = self.send(request).await?.bytes_stream();
+
+        while let Some(chunk) = stream.try_next().await? {
+            cb.emit(chunk.to_vec());
+        }
+
+        Ok(())
+    }
+
+    /// Size of a file from files/stat, dag/stat is used for other nodes.
+    async fn size(&self, path: String, timeout: Duration) -> Result<u64> {
+        let url = self.endpoint("files/stat")?;
+
+        let ipfs_path = format!("/ipfs/{}", path.trim_start_matches("/ipfs/"));
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &ipfs_path), ("timeout", &duration_arg(timeout))]);
+
+        if let Ok(res) = self.json::<FilesStatResponse>(request).await {
+            return Ok(res.size);
+        }
+
+        let url = self.endpoint("dag/stat")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &path), ("timeout", &duration_arg(timeout))])
+            .query(&[("progress", "false")]);
+
+        let res: DagStatResponse = self.json(request).await?;
+
+        Ok(res.size)
+    }
+
+    async fn double_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+        timeout: Duration,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (audio_result, video_result) =
+            join!(self.cat(audio_path, timeout), self.cat(video_path, timeout));
+
+        Ok((audio_result?, video_result?))
+    }
+
+    async fn put(&self, data: String, timeout: Duration) -> Result<Cid> {
+        //Reqwest was hacked to properly format multipart request with text ONLY
+        let form = Form::new().text("object data", data);
+
+        let url = self.endpoint("dag/put")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("timeout", &duration_arg(timeout))])
+            .multipart(form);
+
+        let res: DagPutResponse = self.json(request).await?;
+
+        let cid = Cid::try_from(res.cid.cid_string)?;
+
+        Ok(cid)
+    }
+
+    async fn get(&self, path: String, timeout: Duration) -> Result<Vec<u8>> {
+        let url = self.endpoint("dag/get")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("arg", &path), ("timeout", &duration_arg(timeout))]);
+
+        let bytes = self.send(request).await?.bytes().await?;
+
+        Ok(bytes.to_vec())
+    }
+
+    async fn resolve(&self, ipns: Cid, timeout: Duration) -> Result<Cid> {
+        let url = self.endpoint("name/resolve")?;
+
+        let request = self.client.post(url).query(&[
+            ("arg", &ipns.to_string()),
+            ("timeout", &duration_arg(timeout)),
+        ]);
+
+        let res: NameResolveResponse = self.json(request).await?;
+
+        let cid = Cid::try_from(res.path)?;
+
+        Ok(cid)
+    }
+
+    async fn publish_name(&self, cid: Cid, key: String, timeout: Duration) -> Result<Cid> {
+        let url = self.endpoint("name/publish")?;
+
+        let request = self.client.post(url).query(&[
+            ("arg", &cid.to_string()),
+            ("key", &key),
+            ("timeout", &duration_arg(timeout)),
+        ]);
+
+        let res: NamePublishResponse = self.json(request).await?;
+
+        match peer_id_from_str(&res.name) {
+            Ok(ipns) => Ok(ipns),
+            Err(e) => Err(IpfsError::Decode(e.to_string())),
+        }
+    }
+
+    async fn add_file(
+        &self,
+        data: Vec<u8>,
+        options: AddOptions,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> Result<Cid> {
+        let url = self.endpoint("add")?;
+
+        let form = Form::new().part("file", Part::bytes(data).file_name("file"));
+
+        let cid_version = if options.cid_v1 { "1" } else { "0" };
+        let raw_leaves = if options.raw_leaves { "true" } else { "false" };
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[
+                ("progress", "true"),
+                ("cid-version", cid_version),
+                ("raw-leaves", raw_leaves),
+            ])
+            .query(&[("timeout", &duration_arg(timeout))])
+            .multipart(form);
+
+        let response = self.send(request).await?;
+
+        let mut lines = response.bytes_stream().err_into().into_async_read().lines();
+
+        let mut cid = None;
+
+        while let Some(line) = lines.try_next().await? {
+            if let Ok(ipfs_error) = serde_json::from_str::<IPFSError>(&line) {
+                return Err(ipfs_error.into());
+            }
+
+            let res: AddResponse = serde_json::from_str(&line)?;
+
+            if let Some(hash) = res.hash {
+                cid = Some(Cid::try_from(hash)?);
+            } else if let Some(bytes) = res.bytes {
+                cb.emit(bytes);
+            }
+        }
+
+        match cid {
+            Some(cid) => Ok(cid),
+            None => Err(IpfsError::Decode("No CID in add response".to_owned())),
+        }
+    }
+
+    async fn pin(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let url = self.endpoint("pin/add")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[
+                ("arg", &cid.to_string()),
+                ("recursive", &recursive.to_string()),
+                ("timeout", &duration_arg(timeout)),
+            ])
+            .query(&[("progress", "true")]);
+
+        let response = self.send(request).await?;
+
+        let mut lines = response.bytes_stream().err_into().into_async_read().lines();
+
+        while let Some(line) = lines.try_next().await? {
+            if let Ok(ipfs_error) = serde_json::from_str::<IPFSError>(&line) {
+                return Err(ipfs_error.into());
+            }
+
+            let res: PinAddResponse = serde_json::from_str(&line)?;
+
+            if let Some(progress) = res.progress {
+                cb.emit(progress);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn num_blocks(&self, cid: Cid, timeout: Duration) -> Result<u64> {
+        let url = self.endpoint("dag/stat")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[
+                ("arg", &cid.to_string()),
+                ("timeout", &duration_arg(timeout)),
+            ])
+            .query(&[("progress", "false")]);
+
+        let res: DagStatResponse = self.json(request).await?;
+
+        Ok(res.num_blocks)
+    }
+
+    async fn unpin(&self, cid: Cid, recursive: bool, timeout: Duration) -> Result<()> {
+        let url = self.endpoint("pin/rm")?;
+
+        let request = self.client.post(url).query(&[
+            ("arg", &cid.to_string()),
+            ("recursive", &recursive.to_string()),
+            ("timeout", &duration_arg(timeout)),
+        ]);
+
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    async fn pins(&self, timeout: Duration) -> Result<Vec<Cid>> {
+        let url = self.endpoint("pin/ls")?;
+
+        let request = self
+            .client
+            .post(url)
+            .query(&[("type", "recursive")])
+            .query(&[("timeout", &duration_arg(timeout))]);
+
+        let res: PinLsResponse = self.json(request).await?;
+
+        let mut cids = Vec::with_capacity(res.keys.len());
+
+        for key in res.keys.keys() {
+            cids.push(Cid::try_from(key.as_str())?);
+        }
+
+        Ok(cids)
+    }
+
+    async fn node_id(&self) -> Result<PeerId> {
+        let url = self.endpoint("id")?;
+
+        let res: IdResponse = self.json(self.client.post(url)).await?;
+
+        match peer_id_from_str(&res.id) {
+            Ok(peer_id) => Ok(peer_id),
+            Err(e) => Err(IpfsError::Decode(e.to_string())),
+        }
+    }
+
+    /// Query the daemon version to know which pubsub dialect it speaks.
+    async fn pubsub_dialect(&self) -> Result<PubsubDialect> {
+        if let Some(dialect) = self.dialect.get() {
+            return Ok(dialect);
+        }
+
+        let url = self.endpoint("version")?;
+
+        let res: VersionResponse = self.json(self.client.post(url)).await?;
+
+        let dialect = match parse_version(&res.version) {
+            Some(version) if version < MULTIBASE_PUBSUB_VERSION => PubsubDialect::Legacy,
+            _ => PubsubDialect::Multibase,
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "IPFS: version {} pubsub {:?}",
+            res.version, dialect
+        ));
+
+        self.dialect.set(Some(dialect));
+
+        Ok(dialect)
+    }
+
+    async fn publish(&self, topic: String, msg: String) -> Result<()> {
+        let url = self.endpoint("pubsub/pub")?;
+
+        let request = match self.pubsub_dialect().await? {
+            PubsubDialect::Legacy => self
+                .client
+                .post(url)
+                .query(&[("arg", &topic), ("arg", &msg)]),
+            PubsubDialect::Multibase => {
+                let form = Form::new().text("data", msg);
+
+                self.client
+                    .post(url)
+                    .query(&[("arg", &multibase::encode(Base::Base64Url, topic))])
+                    .multipart(form)
+            }
+        };
+
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: String,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> Result<()> {
+        let result = self.pubsub_stream(&topic, open_cb, cb, regis).await;
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info("Stream Dropped");
+
+        result
+    }
+
+    async fn pubsub_stream(
+        &self,
+        topic: &str,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> Result<()> {
+        let url = self.endpoint("pubsub/sub")?;
+
+        let dialect = self.pubsub_dialect().await?;
+
+        let request = match dialect {
+            PubsubDialect::Legacy => self.client.post(url).query(&[("arg", topic)]),
+            PubsubDialect::Multibase => self
+                .client
+                .post(url)
+                .query(&[("arg", &multibase::encode(Base::Base64Url, topic))]),
+        };
+
+        let response = self.send(request).await?;
+
+        open_cb.emit(());
+
+        let stream = response.bytes_stream();
+
+        let line_stream = stream.err_into().into_async_read().lines();
+
+        let mut abortable_stream = Abortable::new(line_stream, regis);
+
+        while let Some(line) = abortable_stream.try_next().await? {
+            if let Ok(response) = serde_json::from_str::<PubsubSubResponse>(&line) {
+                cb.emit(decode_message(dialect, response));
+
+                continue;
+            }
+
+            // The daemon ends failing streams with an error.
+            let ipfs_error = serde_json::from_str::<IPFSError>(&line)?;
+
+            return Err(ipfs_error.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl IpfsBackend for HttpBackend {
+    fn cid_cat(&self, cid: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(self.cat(cid.to_string(), timeout))
+    }
+
+    fn double_path_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<(Vec<u8>, Vec<u8>)>> {
+        Box::pin(self.double_cat(audio_path, video_path, timeout))
+    }
+
+    fn cat_stream(
+        &self,
+        path: String,
+        cb: Callback<Vec<u8>>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.cat_chunks(path, cb, timeout))
+    }
+
+    fn stat(&self, path: String, timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(self.size(path, timeout))
+    }
+
+    fn dag_put(&self, data: String, timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(self.put(data, timeout))
+    }
+
+    fn dag_get(&self, path: String, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(self.get(path, timeout))
+    }
+
+    fn dag_stat(&self, cid: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(self.num_blocks(cid, timeout))
+    }
+
+    fn name_resolve(&self, ipns: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(self.resolve(ipns, timeout))
+    }
+
+    fn name_publish(
+        &self,
+        cid: Cid,
+        key: String,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(self.publish_name(cid, key, timeout))
+    }
+
+    fn add(
+        &self,
+        data: Vec<u8>,
+        options: AddOptions,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(self.add_file(data, options, cb, timeout))
+    }
+
+    fn pin_add(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.pin(cid, recursive, cb, timeout))
+    }
+
+    fn pin_rm(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.unpin(cid, recursive, timeout))
+    }
+
+    fn pin_ls(&self, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<Cid>>> {
+        Box::pin(self.pins(timeout))
+    }
+
+    fn ipfs_node_id(&self) -> LocalBoxFuture<'_, Result<PeerId>> {
+        Box::pin(self.node_id())
+    }
+
+    fn pubsub_pub(&self, topic: String, msg: String) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.publish(topic, msg))
+    }
+
+    fn pubsub_sub(
+        &self,
+        topic: String,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.subscribe(topic, open_cb, cb, regis))
+    }
+}
+
+/// Decode sender and data of a pubsub message.
+fn decode_message(
+    dialect: PubsubDialect,
+    response: PubsubSubResponse,
+) -> Result<(PeerId, Vec<u8>)> {
+    let PubsubSubResponse { from, data } = response;
+
+    match dialect {
+        PubsubDialect::Legacy => {
+            let from = Base::Base64Pad.decode(from)?;
+            let data = Base::Base64Pad.decode(data)?;
+
+            //Use Peer ID as CID v1 instead of multihash btc58 encoded
+            // https://github.com/libp2p/specs/blob/master/peer-ids/peer-ids.md#string-representation
+            let multihash = MultihashGeneric::from_bytes(&from)?;
+            let cid = Cid::new_v1(0x70, multihash);
+
+            Ok((cid, data))
+        }
+        PubsubDialect::Multibase => {
+            let peer_id = match peer_id_from_str(&from) {
+                Ok(peer_id) => peer_id,
+                Err(e) => return Err(IpfsError::Decode(e.to_string())),
+            };
+
+            let (_, data) = multibase::decode(data)?;
+
+            Ok((peer_id, data))
+        }
+    }
+}
+
+/// Major and minor numbers of a version string like 0.11.0-rc1
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.trim_start_matches('v').split('.');
+
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+
+    Some((major, minor))
+}
+
+#[derive(Deserialize)]
+struct PubsubSubResponse {
+    pub from: String,
+    pub data: String,
+}
+
+#[derive(Deserialize)]
+struct DagPutResponse {
+    #[serde(rename = "Cid")]
+    pub cid: CidString,
+}
+
+#[derive(Deserialize)]
+struct CidString {
+    #[serde(rename = "/")]
+    pub cid_string: String,
+}
+
+#[derive(Deserialize)]
+struct FilesStatResponse {
+    #[serde(rename = "Size")]
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+struct DagStatResponse {
+    #[serde(rename = "Size", alias = "TotalSize")]
+    pub size: u64,
+
+    #[serde(rename = "NumBlocks", alias = "UniqueBlocks", default)]
+    pub num_blocks: u64,
+}
+
+#[derive(Deserialize)]
+struct NameResolveResponse {
+    #[serde(rename = "Path")]
+    pub path: String,
+}
+
+#[derive(Deserialize)]
+struct NamePublishResponse {
+    #[serde(rename = "Name")]
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    pub hash: Option<String>,
+
+    #[serde(rename = "Bytes")]
+    pub bytes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct PinAddResponse {
+    #[serde(rename = "Progress")]
+    pub progress: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct PinLsResponse {
+    #[serde(rename = "Keys")]
+    pub keys: HashMap<String, IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    #[serde(rename = "Version")]
+    pub version: String,
+}
+
+#[derive(Deserialize)]
+struct IdResponse {
+    #[serde(rename = "ID")]
+    pub id: String,
+}
+
+/// Error as returned by the daemon.
+#[derive(Deserialize, Debug)]
+struct IPFSError {
+    #[serde(rename = "Message")]
+    pub message: String,
+
+    #[serde(rename = "Code")]
+    pub code: u64,
+}
+
+/// Go duration string as expected by the daemon's timeout option.
+fn duration_arg(timeout: Duration) -> String {
+    format!("{}ms", timeout.as_millis())
+}
+
+impl From<IPFSError> for IpfsError {
+    fn from(e: IPFSError) -> Self {
+        let message = e.message.to_lowercase();
+
+        if message.contains("context deadline exceeded") {
+            return IpfsError::Timeout;
+        }
+
+        if message.contains("not found")
+            || message.contains("no link named")
+            || message.contains("could not resolve")
+        {
+            return IpfsError::NotFound(e.message);
+        }
+
+        IpfsError::Daemon {
+            code: e.code,
+            message: e.message,
+        }
+    }
+}
+
+impl From<reqwest::Error> for IpfsError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            return IpfsError::Decode(e.to_string());
+        }
+
+        if e.is_timeout() {
+            return IpfsError::Timeout;
+        }
+
+        // Failures sending the request, body failures happen once connected.
+        if e.is_request() {
+            return IpfsError::ConnectionRefused;
+        }
+
+        IpfsError::Transport(e.to_string())
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    future::Future,
+    rc::Rc,
+};
+
+use crate::utils::ipfs::{
+    slot::{Receiver, Slot},
+    IpfsError,
+};
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+type Waiters = HashMap<String, Vec<Rc<Slot<Result<Vec<u8>>>>>>;
+
+/// Coalesce identical concurrent requests.
+#[derive(Clone, Default)]
+pub struct InFlight {
+    requests: Rc<RefCell<Waiters>>,
+}
+
+impl InFlight {
+    /// Run the request unless an identical one is in flight, then share its result.
+    pub async fn coalesce<F>(&self, key: String, request: F) -> Result<Vec<u8>>
+    where
+        F: Future<Output = Result<Vec<u8>>>,
+    {
+        loop {
+            let waiting = match self.requests.borrow_mut().entry(key.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let slot = Slot::new();
+                    entry.get_mut().push(slot.clone());
+
+                    Some(slot)
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(Vec::new());
+
+                    None
+                }
+            };
+
+            let slot = match waiting {
+                Some(slot) => slot,
+                None => break,
+            };
+
+            // The leading request was cancelled, take over.
+            match Receiver(slot).await {
+                Err(IpfsError::Aborted) => continue,
+                result => return result,
+            }
+        }
+
+        let mut guard = Guard {
+            requests: self.requests.clone(),
+            key,
+            result: None,
+        };
+
+        let result = request.await;
+
+        guard.result = Some(result.clone());
+
+        result
+    }
+}
+
+/// Share the result with waiting requests, even if the leading one is dropped.
+struct Guard {
+    requests: Rc<RefCell<Waiters>>,
+    key: String,
+    result: Option<Result<Vec<u8>>>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let waiters = self.requests.borrow_mut().remove(&self.key);
+
+        let result = self.result.take().unwrap_or(Err(IpfsError::Aborted));
+
+        for slot in waiters.into_iter().flatten() {
+            slot.send(result.clone());
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    convert::TryFrom,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+use crate::utils::ipfs::{AddOptions, IpfsBackend, IpfsError};
+
+use futures_util::future::{pending, AbortRegistration, Abortable, LocalBoxFuture};
+
+use serde_json::Value;
+
+use yew::Callback;
+
+use cid::{multihash::MultihashGeneric, Cid};
+
+use linked_data::PeerId;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+type Subscriber = (usize, Callback<Result<(PeerId, Vec<u8>)>>);
+
+/// Multicodec private use range, the in-memory hash is not a real hash function.
+const MEMORY_HASH_CODE: u64 = 0x30_0000;
+
+const RAW_CODEC: u64 = 0x55;
+const DAG_JSON_CODEC: u64 = 0x0129;
+const LIBP2P_KEY_CODEC: u64 = 0x72;
+const PEER_ID_CODEC: u64 = 0x70;
+
+/// IPFS node living entirely in memory, for tests.
+///
+/// Blocks, IPNS records and pubsub topics are never shared outside this instance.
+/// Content from the network can be seeded under its real CID.
+pub struct MemoryBackend {
+    peer_id: PeerId,
+
+    blocks: RefCell<HashMap<Cid, Vec<u8>>>,
+    pins: RefCell<HashSet<Cid>>,
+
+    /// Maps IPNS to CID
+    names: RefCell<HashMap<Cid, Cid>>,
+
+    /// Maps topics to subscribers
+    topics: RefCell<HashMap<String, Vec<Subscriber>>>,
+    next_sub: Cell<usize>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        let peer_id = memory_cid(PEER_ID_CODEC, b"in-memory ipfs node");
+
+        Self {
+            peer_id,
+
+            blocks: RefCell::new(HashMap::with_capacity(100)),
+            pins: RefCell::new(HashSet::with_capacity(10)),
+            names: RefCell::new(HashMap::with_capacity(10)),
+
+            topics: RefCell::new(HashMap::with_capacity(10)),
+            next_sub: Cell::new(0),
+        }
+    }
+
+    /// Store this block as is under its CID, raw bytes or dag-json.
+    pub fn seed_block(&self, cid: Cid, data: Vec<u8>) {
+        self.blocks.borrow_mut().insert(cid, data);
+    }
+
+    /// Point this IPNS link to a CID.
+    pub fn seed_name(&self, ipns: Cid, cid: Cid) {
+        self.names.borrow_mut().insert(ipns, cid);
+    }
+
+    fn get_block(&self, cid: &Cid) -> Result<Vec<u8>> {
+        match self.blocks.borrow().get(cid) {
+            Some(block) => Ok(block.clone()),
+            None => Err(IpfsError::NotFound(cid.to_string())),
+        }
+    }
+
+    /// Walk the path through dag nodes, following links.
+    ///
+    /// Returns the last CID crossed and the value at the end of the path.
+    fn walk(&self, path: &str) -> Result<(Cid, Value)> {
+        let mut segments = path
+            .trim_start_matches("/ipfs/")
+            .split('/')
+            .filter(|s| !s.is_empty());
+
+        let mut cid = match segments.next() {
+            Some(root) => Cid::try_from(root)?,
+            None => return Err(IpfsError::NotFound(path.to_owned())),
+        };
+
+        let mut value: Value = serde_json::from_slice(&self.get_block(&cid)?)?;
+
+        for segment in segments {
+            if let Some(link) = as_link(&value) {
+                cid = link;
+                value = serde_json::from_slice(&self.get_block(&cid)?)?;
+            }
+
+            value = match value {
+                Value::Object(mut map) => map.remove(segment),
+                Value::Array(mut array) => match segment.parse::<usize>() {
+                    Ok(index) if index < array.len() => Some(array.swap_remove(index)),
+                    _ => None,
+                },
+                _ => None,
+            }
+            .ok_or_else(|| IpfsError::NotFound(path.to_owned()))?;
+        }
+
+        Ok((cid, value))
+    }
+
+    fn cat(&self, path: &str) -> Result<Vec<u8>> {
+        // Raw blocks are not dag nodes.
+        if let Ok(cid) = Cid::try_from(path.trim_start_matches("/ipfs/")) {
+            return self.get_block(&cid);
+        }
+
+        let (cid, value) = self.walk(path)?;
+
+        match as_link(&value) {
+            Some(link) => self.get_block(&link),
+            None => self.get_block(&cid),
+        }
+    }
+
+    fn get(&self, path: &str) -> Result<Vec<u8>> {
+        let (_, value) = self.walk(path)?;
+
+        let value = match as_link(&value) {
+            Some(link) => serde_json::from_slice(&self.get_block(&link)?)?,
+            None => value,
+        };
+
+        let data = serde_json::to_vec(&value)?;
+
+        Ok(data)
+    }
+
+    fn put(&self, data: String) -> Result<Cid> {
+        // Round trip to validate the data.
+        let value: Value = serde_json::from_str(&data)?;
+        let block = serde_json::to_vec(&value)?;
+
+        let cid = memory_cid(DAG_JSON_CODEC, &block);
+
+        self.blocks.borrow_mut().insert(cid, block);
+
+        Ok(cid)
+    }
+
+    /// Files are stored as a single raw block, options are ignored.
+    fn add_file(&self, data: Vec<u8>) -> Cid {
+        let cid = memory_cid(RAW_CODEC, &data);
+
+        self.blocks.borrow_mut().insert(cid, data);
+
+        cid
+    }
+
+    fn pin(&self, cid: Cid) -> Result<()> {
+        self.get_block(&cid)?;
+
+        self.pins.borrow_mut().insert(cid);
+
+        Ok(())
+    }
+
+    fn unpin(&self, cid: Cid) -> Result<()> {
+        if !self.pins.borrow_mut().remove(&cid) {
+            return Err(IpfsError::NotFound(cid.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, ipns: Cid) -> Result<Cid> {
+        match self.names.borrow().get(&ipns) {
+            Some(cid) => Ok(*cid),
+            None => Err(IpfsError::NotFound(ipns.to_string())),
+        }
+    }
+
+    fn publish(&self, topic: String, msg: String) {
+        let subscribers = match self.topics.borrow().get(&topic) {
+            Some(subs) => subs.iter().map(|(_, cb)| cb.clone()).collect::<Vec<_>>(),
+            None => return,
+        };
+
+        let data = msg.into_bytes();
+
+        for cb in subscribers {
+            cb.emit(Ok((self.peer_id, data.clone())));
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        topic: String,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> Result<()> {
+        let id = self.next_sub.get();
+        self.next_sub.set(id + 1);
+
+        self.topics
+            .borrow_mut()
+            .entry(topic.clone())
+            .or_default()
+            .push((id, cb));
+
+        open_cb.emit(());
+
+        // Stay subscribed until aborted.
+        let _ = Abortable::new(pending::<()>(), regis).await;
+
+        if let Some(subs) = self.topics.borrow_mut().get_mut(&topic) {
+            subs.retain(|(sub_id, _)| *sub_id != id);
+        }
+
+        Ok(())
+    }
+
+    /// Point the IPNS name derived from this key to a CID.
+    fn publish_name(&self, cid: Cid, key: &str) -> Cid {
+        // Keys can be named or be the IPNS link itself.
+        let ipns = match Cid::try_from(key) {
+            Ok(ipns) => ipns,
+            Err(_) => memory_cid(LIBP2P_KEY_CODEC, key.as_bytes()),
+        };
+
+        self.names.borrow_mut().insert(ipns, cid);
+
+        ipns
+    }
+}
+
+impl IpfsBackend for MemoryBackend {
+    fn cid_cat(&self, cid: Cid, _timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(async move { self.get_block(&cid) })
+    }
+
+    fn double_path_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<(Vec<u8>, Vec<u8>)>> {
+        Box::pin(async move { Ok((self.cat(&audio_path)?, self.cat(&video_path)?)) })
+    }
+
+    fn cat_stream(
+        &self,
+        path: String,
+        cb: Callback<Vec<u8>>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            cb.emit(self.cat(&path)?);
+
+            Ok(())
+        })
+    }
+
+    fn stat(&self, path: String, _timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(async move { Ok(self.cat(&path)?.len() as u64) })
+    }
+
+    fn dag_put(&self, data: String, _timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async move { self.put(data) })
+    }
+
+    fn dag_get(&self, path: String, _timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(async move { self.get(&path) })
+    }
+
+    /// Links are not followed, each node is a single block.
+    fn dag_stat(&self, cid: Cid, _timeout: Duration) -> LocalBoxFuture<'_, Result<u64>> {
+        Box::pin(async move {
+            self.get_block(&cid)?;
+
+            Ok(1)
+        })
+    }
+
+    fn name_resolve(&self, ipns: Cid, _timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async move { self.resolve(ipns) })
+    }
+
+    fn name_publish(
+        &self,
+        cid: Cid,
+        key: String,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async move { Ok(self.publish_name(cid, &key)) })
+    }
+
+    fn add(
+        &self,
+        data: Vec<u8>,
+        _options: AddOptions,
+        cb: Callback<u64>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>> {
+        Box::pin(async move {
+            cb.emit(data.len() as u64);
+
+            Ok(self.add_file(data))
+        })
+    }
+
+    fn pin_add(
+        &self,
+        cid: Cid,
+        _recursive: bool,
+        cb: Callback<u64>,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.pin(cid)?;
+
+            cb.emit(1);
+
+            Ok(())
+        })
+    }
+
+    fn pin_rm(
+        &self,
+        cid: Cid,
+        _recursive: bool,
+        _timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.unpin(cid) })
+    }
+
+    fn pin_ls(&self, _timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<Cid>>> {
+        Box::pin(async move { Ok(self.pins.borrow().iter().copied().collect()) })
+    }
+
+    fn ipfs_node_id(&self) -> LocalBoxFuture<'_, Result<PeerId>> {
+        Box::pin(async move { Ok(self.peer_id) })
+    }
+
+    fn pubsub_pub(&self, topic: String, msg: String) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.publish(topic, msg);
+
+            Ok(())
+        })
+    }
+
+    fn pubsub_sub(
+        &self,
+        topic: String,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.subscribe(topic, open_cb, cb, regis))
+    }
+}
+
+/// Returns the CID in a dag-json link object.
+fn as_link(value: &Value) -> Option<Cid> {
+    let map = value.as_object()?;
+
+    if map.len() != 1 {
+        return None;
+    }
+
+    let link = map.get("/")?.as_str()?;
+
+    Cid::try_from(link).ok()
+}
+
+/// CID from a deterministic 32 bytes digest, NOT cryptographically secure.
+fn memory_cid(codec: u64, data: &[u8]) -> Cid {
+    let mut digest = [0u8; 32];
+
+    for (seed, chunk) in digest.chunks_mut(8).enumerate() {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        data.hash(&mut hasher);
+
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+
+    match MultihashGeneric::wrap(MEMORY_HASH_CODE, &digest) {
+        Ok(multihash) => Cid::new_v1(codec, multihash),
+        Err(_) => unreachable!("32 bytes digest always fit"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::ipfs::IpfsService;
+
+    use linked_data::{
+        beacon::Beacon,
+        comments::{Comment, Commentary},
+        feed::FeedAnchor,
+        IPLDLink,
+    };
+
+    use serde_json::json;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn seed_node(backend: &MemoryBackend, node: &Value) -> Cid {
+        let block = serde_json::to_vec(node).unwrap();
+        let cid = memory_cid(DAG_JSON_CODEC, &block);
+
+        backend.seed_block(cid, block);
+
+        cid
+    }
+
+    fn seed_ipns(backend: &MemoryBackend, key: &str, cid: Cid) -> Cid {
+        let ipns = memory_cid(LIBP2P_KEY_CODEC, key.as_bytes());
+
+        backend.seed_name(ipns, cid);
+
+        ipns
+    }
+
+    fn link(cid: Cid) -> Value {
+        json!({ "/": cid.to_string() })
+    }
+
+    /// Seed a beacon with one post and one comment on it. Return the beacon, post and comment CIDs.
+    fn seed_beacon(backend: &MemoryBackend) -> (Cid, Cid, Cid) {
+        let markdown = memory_cid(RAW_CODEC, b"# Hello");
+        backend.seed_block(markdown, b"# Hello".to_vec());
+
+        let post = seed_node(backend, &json!({ "content": link(markdown) }));
+
+        let feed = seed_node(backend, &json!({ "content": [link(post)] }));
+        let feed_ipns = seed_ipns(backend, "feed", feed);
+
+        let comment = Comment {
+            timestamp: 0,
+            origin: IPLDLink { link: post },
+            comment: "First!".to_owned(),
+        };
+        let comment = seed_node(backend, &serde_json::to_value(&comment).unwrap());
+
+        let commentary = seed_node(
+            backend,
+            &json!({ "comments": { post.to_string(): [link(comment)] } }),
+        );
+        let comments_ipns = seed_ipns(backend, "comments", commentary);
+
+        let identity = seed_node(backend, &json!({ "display_name": "Tester" }));
+        let identity_ipns = seed_ipns(backend, "identity", identity);
+
+        let beacon = seed_node(
+            backend,
+            &json!({
+                "identity": identity_ipns.to_string(),
+                "content_feed": feed_ipns.to_string(),
+                "comments": comments_ipns.to_string(),
+            }),
+        );
+
+        (beacon, post, comment)
+    }
+
+    #[wasm_bindgen_test]
+    async fn beacon_resolves_to_feed_and_comments() {
+        let backend = MemoryBackend::new();
+        let (beacon_cid, post, comment_cid) = seed_beacon(&backend);
+
+        let ipfs = IpfsService::from_backend(backend);
+
+        let beacon: Beacon = ipfs
+            .dag_get(beacon_cid, Option::<&str>::None)
+            .await
+            .unwrap();
+
+        let (_, feed): (Cid, FeedAnchor) = ipfs
+            .resolve_and_dag_get(beacon.content_feed.unwrap())
+            .await
+            .unwrap();
+
+        let posts: Vec<Cid> = feed.content.iter().map(|ipld| ipld.link).collect();
+
+        assert_eq!(posts, vec![post]);
+
+        let (_, commentary): (Cid, Commentary) = ipfs
+            .resolve_and_dag_get(beacon.comments.unwrap())
+            .await
+            .unwrap();
+
+        let comments: Vec<Cid> = commentary.comments[&post.to_string()]
+            .iter()
+            .map(|ipld| ipld.link)
+            .collect();
+
+        assert_eq!(comments, vec![comment_cid]);
+
+        let comment: Comment = ipfs
+            .dag_get(comment_cid, Option::<&str>::None)
+            .await
+            .unwrap();
+
+        assert_eq!(comment.origin.link, post);
+        assert_eq!(comment.comment, "First!");
+    }
+
+    #[wasm_bindgen_test]
+    async fn post_content_is_reachable_from_its_link() {
+        let backend = MemoryBackend::new();
+        let (_, post, _) = seed_beacon(&backend);
+
+        let ipfs = IpfsService::from_backend(backend);
+
+        let node: Value = ipfs.dag_get(post, Option::<&str>::None).await.unwrap();
+        let markdown = as_link(&node["content"]).unwrap();
+
+        let data = ipfs.cid_cat(markdown).await.unwrap();
+
+        assert_eq!(data, b"# Hello".to_vec());
+    }
+
+    #[wasm_bindgen_test]
+    async fn unknown_names_are_not_found() {
+        let backend = MemoryBackend::new();
+        let ipns = memory_cid(LIBP2P_KEY_CODEC, b"nobody");
+
+        let ipfs = IpfsService::from_backend(backend);
+
+        let result = ipfs.resolve_and_dag_get::<Beacon>(ipns).await;
+
+        assert!(matches!(result, Err(IpfsError::NotFound(_))));
+    }
+}
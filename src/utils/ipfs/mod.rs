@@ -0,0 +1,1030 @@
+mod cache;
+mod cancel;
+mod error;
+mod gateway;
+mod http;
+mod in_flight;
+#[cfg(test)]
+mod memory;
+mod persistent;
+mod scheduler;
+mod slot;
+
+#[cfg(debug_assertions)]
+pub use cache::CacheStats;
+pub use cancel::CancelToken;
+pub use error::IpfsError;
+pub use gateway::{gateway_url, test_gateway, DEFAULT_GATEWAYS};
+pub use http::DEFAULT_URI;
+pub use scheduler::{Priority, DEFAULT_MAX_REQUESTS};
+
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    convert::TryFrom,
+    future::Future,
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::utils::{local_storage::LocalStorage, timer::sleep};
+
+use cache::{BlockCache, DEFAULT_CACHE_SIZE};
+use gateway::GatewayBackend;
+use http::HttpBackend;
+use in_flight::InFlight;
+use persistent::{PersistentCache, DEFAULT_PERSISTENT_SIZE};
+use scheduler::Scheduler;
+
+use futures_util::future::{self, AbortHandle, AbortRegistration, Abortable, LocalBoxFuture};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use yew::{services::ConsoleService, Callback};
+
+use cid::Cid;
+
+use linked_data::PeerId;
+
+use reqwest::Url;
+
+/// Default deadline of each request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// IPNS publishing is slow, it never uses a shorter deadline.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Uploads and pins move whole files, they never use a shorter deadline.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Reads are retried this many times after the first attempt.
+const MAX_RETRIES: u32 = 3;
+
+/// Delay before the first retry, doubled after each attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Subscriptions are given up after this many failed attempts in a row.
+const MAX_RESUBSCRIBE: u32 = 8;
+
+/// Delay before the first resubscription, doubled after each attempt.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(30);
+
+/// Milliseconds between reads trying the daemon again in read-only mode.
+const DAEMON_PROBE_INTERVAL: f64 = 30_000.0;
+
+type Result<T> = std::result::Result<T, IpfsError>;
+
+/// Operations an IPFS node must provide.
+///
+/// Paths and nodes are raw, (de)serialization is done by IpfsService.
+pub trait IpfsBackend {
+    /// Download content from block with this CID.
+    fn cid_cat(&self, cid: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>>;
+
+    /// Download content simultaneously from 2 paths.
+    fn double_path_cat(
+        &self,
+        audio_path: String,
+        video_path: String,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<(Vec<u8>, Vec<u8>)>>;
+
+    /// Download content at this path, chunks are sent to the callback as they arrive.
+    fn cat_stream(
+        &self,
+        path: String,
+        cb: Callback<Vec<u8>>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// Return the size in bytes of the content at this path.
+    fn stat(&self, path: String, timeout: Duration) -> LocalBoxFuture<'_, Result<u64>>;
+
+    /// Add JSON dag node to IPFS. Return a CID.
+    fn dag_put(&self, data: String, timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>>;
+
+    /// Return JSON dag node at this IPFS path.
+    fn dag_get(&self, path: String, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<u8>>>;
+
+    /// Return the number of blocks in the DAG under this CID.
+    fn dag_stat(&self, cid: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<u64>>;
+
+    /// Resolve IPNS link to a CID.
+    fn name_resolve(&self, ipns: Cid, timeout: Duration) -> LocalBoxFuture<'_, Result<Cid>>;
+
+    /// Point the IPNS link of this key to a CID. Return the IPNS link.
+    fn name_publish(
+        &self,
+        cid: Cid,
+        key: String,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>>;
+
+    /// Upload a file. Bytes processed are sent to the callback. Return a CID.
+    fn add(
+        &self,
+        data: Vec<u8>,
+        options: AddOptions,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<Cid>>;
+
+    /// Pin this CID. Number of nodes fetched are sent to the callback.
+    fn pin_add(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        cb: Callback<u64>,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>>;
+
+    fn pin_rm(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        timeout: Duration,
+    ) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// Return recursively pinned CIDs.
+    fn pin_ls(&self, timeout: Duration) -> LocalBoxFuture<'_, Result<Vec<Cid>>>;
+
+    fn ipfs_node_id(&self) -> LocalBoxFuture<'_, Result<PeerId>>;
+
+    fn pubsub_pub(&self, topic: String, msg: String) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// Subscribe to a topic, messages are sent to the callback until aborted.
+    ///
+    /// The open callback is called once the subscription is accepted.
+    /// Return when the stream ends.
+    fn pubsub_sub(
+        &self,
+        topic: String,
+        open_cb: Callback<()>,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        regis: AbortRegistration,
+    ) -> LocalBoxFuture<'_, Result<()>>;
+}
+
+/// Chunk of a streaming download.
+#[derive(Clone, Debug)]
+pub struct CatProgress {
+    pub chunk: Vec<u8>,
+
+    /// Bytes received so far, this chunk included.
+    pub loaded: u64,
+
+    /// Total size in bytes, if the node could tell.
+    pub total: Option<u64>,
+
+    /// Previous chunks must be discarded, the download started over from another source.
+    pub restart: bool,
+}
+
+/// Options of the add request.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct AddOptions {
+    pub cid_v1: bool,
+    pub raw_leaves: bool,
+}
+
+/// State of a pubsub subscription.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PubsubState {
+    Connected,
+
+    /// The stream dropped, next attempt in this many seconds.
+    Reconnecting(u64),
+
+    /// Too many attempts failed, the subscription was given up.
+    Failed(IpfsError),
+}
+
+#[derive(Clone)]
+pub struct IpfsService {
+    backend: Rc<dyn IpfsBackend>,
+
+    /// Used for reads when the backend cannot be reached.
+    gateway: Option<Rc<GatewayBackend>>,
+    read_only: Rc<Cell<bool>>,
+    /// Milliseconds since epoch of the last daemon attempt in read-only mode.
+    probed: Rc<Cell<f64>>,
+    read_only_cb: Rc<RefCell<Callback<bool>>>,
+
+    cache: Rc<RefCell<BlockCache>>,
+    persistent: Option<Rc<PersistentCache>>,
+
+    in_flight: InFlight,
+    scheduler: Scheduler,
+    priority: Priority,
+
+    timeout: Duration,
+    token: Option<CancelToken>,
+}
+
+impl IpfsService {
+    pub fn new(storage: &LocalStorage) -> Self {
+        let addrs = match storage.get_local_ipfs_addrs() {
+            Some(addrs) => addrs,
+            None => {
+                storage.set_local_ipfs_addrs(DEFAULT_URI);
+
+                DEFAULT_URI.to_owned()
+            }
+        };
+
+        let url = match Url::parse(&addrs) {
+            Ok(url) => url,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                std::process::abort();
+            }
+        };
+
+        let gateways = match storage.get_gateways() {
+            Some(gateways) => gateways,
+            None => DEFAULT_GATEWAYS.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let gateways = gateways.iter().filter_map(|s| gateway_url(s)).collect();
+
+        let max_requests = storage.get_max_requests().unwrap_or(DEFAULT_MAX_REQUESTS);
+
+        let persistent = if storage.get_persistent_cache() {
+            Some(PersistentCache::new(DEFAULT_PERSISTENT_SIZE))
+        } else {
+            None
+        };
+
+        Self {
+            backend: Rc::new(HttpBackend::new(url)),
+            gateway: Some(Rc::new(GatewayBackend::new(gateways))),
+            read_only: Rc::new(Cell::new(false)),
+            probed: Rc::new(Cell::new(0.0)),
+            read_only_cb: Rc::new(RefCell::new(Callback::noop())),
+
+            cache: Rc::new(RefCell::new(BlockCache::new(DEFAULT_CACHE_SIZE))),
+            persistent,
+
+            in_flight: InFlight::default(),
+            scheduler: Scheduler::new(max_requests),
+            priority: Priority::Normal,
+
+            timeout: DEFAULT_TIMEOUT,
+            token: None,
+        }
+    }
+
+    /// Service over any backend, like the in-memory node used in tests.
+    #[cfg(test)]
+    pub fn from_backend<B>(backend: B) -> Self
+    where
+        B: IpfsBackend + 'static,
+    {
+        Self {
+            backend: Rc::new(backend),
+            gateway: None,
+            read_only: Rc::new(Cell::new(false)),
+            probed: Rc::new(Cell::new(0.0)),
+            read_only_cb: Rc::new(RefCell::new(Callback::noop())),
+
+            cache: Rc::new(RefCell::new(BlockCache::new(DEFAULT_CACHE_SIZE))),
+            persistent: None,
+
+            in_flight: InFlight::default(),
+            scheduler: Scheduler::new(DEFAULT_MAX_REQUESTS),
+            priority: Priority::Normal,
+
+            timeout: DEFAULT_TIMEOUT,
+            token: None,
+        }
+    }
+
+    /// Return a handle sending requests with this priority.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Return a handle giving up on each request after this duration.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    /// Return a handle whose requests are aborted when the token is cancelled.
+    pub fn with_token(&self, token: &CancelToken) -> Self {
+        Self {
+            token: Some(token.clone()),
+            ..self.clone()
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.borrow().stats()
+    }
+
+    /// Return cached data, from memory first then IndexedDB.
+    async fn cache_get(&self, key: &str) -> Option<Vec<u8>> {
+        if let Some(data) = self.cache.borrow_mut().get(key) {
+            return Some(data);
+        }
+
+        let data = self.persistent.as_ref()?.get(key).await?;
+
+        let mut cache = self.cache.borrow_mut();
+
+        #[cfg(debug_assertions)]
+        cache.persistent_hit();
+
+        cache.insert(key.to_owned(), data.clone());
+
+        Some(data)
+    }
+
+    async fn cache_insert(&self, key: String, data: &[u8]) {
+        if let Some(persistent) = self.persistent.as_ref() {
+            persistent.insert(&key, data).await;
+        }
+
+        self.cache.borrow_mut().insert(key, data.to_vec());
+    }
+
+    /// True when content is read from public gateways instead of a daemon.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.get()
+    }
+
+    /// Send read-only mode changes to this callback, replacing the previous one.
+    pub fn on_read_only(&self, cb: Callback<bool>) {
+        *self.read_only_cb.borrow_mut() = cb;
+    }
+
+    fn set_read_only(&self, read_only: bool) {
+        if self.gateway.is_none() {
+            return;
+        }
+
+        if read_only {
+            self.probed.set(js_sys::Date::now());
+        }
+
+        if self.read_only.replace(read_only) != read_only {
+            let cb = self.read_only_cb.borrow().clone();
+
+            cb.emit(read_only);
+        }
+    }
+
+    /// True if the daemon should be tried again while in read-only mode.
+    fn probe_daemon(&self) -> bool {
+        let now = js_sys::Date::now();
+
+        if now - self.probed.get() < DAEMON_PROBE_INTERVAL {
+            return false;
+        }
+
+        self.probed.set(now);
+
+        true
+    }
+
+    /// Abort the request if the token is cancelled.
+    async fn cancellable<T, F>(&self, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let token = match &self.token {
+            Some(token) => token,
+            None => return request.await,
+        };
+
+        let (_registration, regis) = token.register();
+
+        match Abortable::new(request, regis).await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Fail the request if it does not complete before the deadline.
+    async fn deadline<T, F>(&self, request: F, timeout: Duration) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let request = Box::pin(request);
+        let timer = Box::pin(sleep(timeout));
+
+        match future::select(request, timer).await {
+            future::Either::Left((result, _)) => result,
+            future::Either::Right(_) => Err(IpfsError::Timeout),
+        }
+    }
+
+    /// Run an idempotent read, retrying transient errors with exponential backoff.
+    async fn read<T, F>(&self, op: F) -> Result<T>
+    where
+        F: Fn(&dyn IpfsBackend, Duration) -> LocalBoxFuture<'_, Result<T>>,
+    {
+        let mut delay = RETRY_DELAY;
+        let mut retries = 0;
+
+        loop {
+            match self.read_once(&op).await {
+                Err(e) if e.is_retryable() && retries < MAX_RETRIES => {
+                    #[cfg(debug_assertions)]
+                    ConsoleService::warn(&format!("IPFS: {} retry in {:?}", e, delay));
+
+                    sleep(delay).await;
+
+                    delay *= 2;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run a read operation, falling back to gateways if the daemon cannot be reached.
+    async fn read_once<T, F>(&self, op: &F) -> Result<T>
+    where
+        F: Fn(&dyn IpfsBackend, Duration) -> LocalBoxFuture<'_, Result<T>>,
+    {
+        let _permit = self.scheduler.acquire(self.priority).await;
+
+        let gateway = match &self.gateway {
+            Some(gateway) => gateway,
+            None => {
+                return self
+                    .deadline(op(&*self.backend, self.timeout), self.timeout)
+                    .await
+            }
+        };
+
+        if !self.read_only.get() || self.probe_daemon() {
+            match self
+                .deadline(op(&*self.backend, self.timeout), self.timeout)
+                .await
+            {
+                Ok(data) => {
+                    self.set_read_only(false);
+
+                    return Ok(data);
+                }
+                Err(IpfsError::ConnectionRefused) | Err(IpfsError::Blocked) => {
+                    self.set_read_only(true)
+                }
+                Err(e) if !self.read_only.get() => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        self.deadline(op(&**gateway, self.timeout), self.timeout)
+            .await
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only.get() {
+            return Err(IpfsError::ReadOnly);
+        }
+
+        Ok(())
+    }
+
+    /// Download content from block with this CID.
+    pub async fn cid_cat(&self, cid: Cid) -> Result<Vec<u8>> {
+        self.cancellable(async {
+            let key = format!("cat:{}", cid);
+
+            if let Some(data) = self.cache_get(&key).await {
+                return Ok(data);
+            }
+
+            let request = async {
+                let data = self
+                    .read(|ipfs, timeout| ipfs.cid_cat(cid, timeout))
+                    .await?;
+
+                self.cache_insert(key.clone(), &data).await;
+
+                Ok::<_, IpfsError>(data)
+            };
+
+            self.in_flight
+                .coalesce(format!("cat {}", cid), request)
+                .await
+        })
+        .await
+    }
+
+    /// Download content simultaneously from 2 paths.
+    pub async fn double_path_cat<U>(
+        &self,
+        audio_path: U,
+        video_path: U,
+    ) -> Result<(Vec<u8>, Vec<u8>)>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        let audio_path = audio_path.into().into_owned();
+        let video_path = video_path.into().into_owned();
+
+        self.cancellable(self.read(|ipfs, timeout| {
+            ipfs.double_path_cat(audio_path.clone(), video_path.clone(), timeout)
+        }))
+        .await
+    }
+
+    /// Download content at this path, sending chunks to the callback as they arrive.
+    ///
+    /// Return all the content once complete. Streams are never retried nor coalesced,
+    /// only content addressed by a bare CID is cached.
+    pub async fn cat_stream<U>(&self, path: U, cb: Callback<CatProgress>) -> Result<Vec<u8>>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        let path = path.into().into_owned();
+        let cacheable = Cid::try_from(path.as_str()).is_ok();
+        let key = format!("cat:{}", path);
+
+        self.cancellable(async {
+            let cached = if cacheable {
+                self.cache_get(&key).await
+            } else {
+                None
+            };
+
+            if let Some(data) = cached {
+                let size = data.len() as u64;
+
+                cb.emit(CatProgress {
+                    chunk: data.clone(),
+                    loaded: size,
+                    total: Some(size),
+                    restart: false,
+                });
+
+                return Ok(data);
+            }
+
+            // Progress without total is still useful, errors are ignored.
+            let total = Rc::new(Cell::new(None));
+
+            let stat = async {
+                if let Ok(size) = self
+                    .read_once(&|ipfs, timeout| ipfs.stat(path.clone(), timeout))
+                    .await
+                {
+                    total.set(Some(size));
+                }
+            };
+
+            let data = Rc::new(RefCell::new(Vec::new()));
+
+            let chunk_cb = Callback::from({
+                let data = data.clone();
+                let total = total.clone();
+                let cb = cb.clone();
+
+                move |chunk: Vec<u8>| {
+                    let loaded = {
+                        let mut data = data.borrow_mut();
+                        data.extend_from_slice(&chunk);
+
+                        data.len() as u64
+                    };
+
+                    cb.emit(CatProgress {
+                        chunk,
+                        loaded,
+                        total: total.get(),
+                        restart: false,
+                    });
+                }
+            });
+
+            let cat = async {
+                self.read_once(&|ipfs, timeout| {
+                    // The daemon failed midway, gateways stream from the start.
+                    if !data.borrow().is_empty() {
+                        data.borrow_mut().clear();
+
+                        cb.emit(CatProgress {
+                            chunk: Vec::new(),
+                            loaded: 0,
+                            total: total.get(),
+                            restart: true,
+                        });
+                    }
+
+                    ipfs.cat_stream(path.clone(), chunk_cb.clone(), timeout)
+                })
+                .await
+            };
+
+            // Stat runs alongside the first bytes, it is dropped once the content is complete.
+            match future::select(Box::pin(cat), Box::pin(stat)).await {
+                future::Either::Left((result, _)) => result?,
+                future::Either::Right((_, cat)) => cat.await?,
+            }
+
+            let data = data.take();
+
+            if cacheable {
+                self.cache_insert(key.clone(), &data).await;
+            }
+
+            Ok(data)
+        })
+        .await
+    }
+
+    /// Return the size in bytes of the content at this path.
+    pub async fn stat<U>(&self, path: U) -> Result<u64>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        let path = path.into().into_owned();
+
+        self.cancellable(self.read(|ipfs, timeout| ipfs.stat(path.clone(), timeout)))
+            .await
+    }
+
+    /// Serialize then add dag node to IPFS. Return a CID.
+    pub async fn dag_put<T>(&self, node: &T) -> Result<Cid>
+    where
+        T: ?Sized + Serialize,
+    {
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Serde: Serialize => {}",
+            serde_json::to_string(node).unwrap()
+        ));
+
+        self.check_writable()?;
+
+        let data = serde_json::to_string(node)?;
+
+        let cid = self
+            .cancellable(async {
+                let _permit = self.scheduler.acquire(self.priority).await;
+
+                self.deadline(self.backend.dag_put(data, self.timeout), self.timeout)
+                    .await
+            })
+            .await?;
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("IPFS: dag put => {}", cid));
+
+        Ok(cid)
+    }
+
+    /// Deserialize dag node from IPFS path. Return dag node.
+    pub async fn dag_get<U, T>(&self, cid: Cid, path: Option<U>) -> Result<T>
+    where
+        U: Into<Cow<'static, str>>,
+        T: ?Sized + DeserializeOwned,
+    {
+        let mut origin = cid.to_string();
+
+        if let Some(path) = path {
+            origin.push_str(&path.into());
+        }
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("IPFS: dag get => {}", origin));
+
+        let key = format!("dag:{}", origin);
+
+        let data = self
+            .cancellable(async {
+                if let Some(data) = self.cache_get(&key).await {
+                    return Ok(data);
+                }
+
+                let request = async {
+                    let data = self
+                        .read(|ipfs, timeout| ipfs.dag_get(origin.clone(), timeout))
+                        .await?;
+
+                    self.cache_insert(key.clone(), &data).await;
+
+                    Ok::<_, IpfsError>(data)
+                };
+
+                self.in_flight
+                    .coalesce(format!("dag get {}", origin), request)
+                    .await
+            })
+            .await?;
+
+        let node = serde_json::from_slice::<T>(&data)?;
+
+        Ok(node)
+    }
+
+    /// Resolve IPNS link then dag get. Return IPNS link, CID & Node.
+    pub async fn resolve_and_dag_get<T>(&self, ipns: Cid) -> Result<(Cid, T)>
+    where
+        T: ?Sized + DeserializeOwned,
+    {
+        let cid = self
+            .cancellable(self.read(|ipfs, timeout| ipfs.name_resolve(ipns, timeout)))
+            .await?;
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("IPFS: name resolve {} \n to {}", ipns, cid));
+
+        let node = self.dag_get(cid, Option::<&str>::None).await?;
+
+        Ok((cid, node))
+    }
+
+    /// Send the CID each IPNS link points to when it changes from the last one known.
+    ///
+    /// Links are resolved one after the other in a single loop, holding at most one connection.
+    /// Nodes with namesys pubsub enabled resolve from their own subscriptions and see updates sooner.
+    /// Run until the token is cancelled.
+    pub async fn name_watch(
+        &self,
+        mut links: Vec<(Cid, Option<Cid>, Callback<Cid>)>,
+        interval: Duration,
+    ) {
+        let _ = self
+            .cancellable(async {
+                loop {
+                    sleep(interval).await;
+
+                    for (ipns, last, cb) in links.iter_mut() {
+                        let ipns = *ipns;
+
+                        match self
+                            .read(|ipfs, timeout| ipfs.name_resolve(ipns, timeout))
+                            .await
+                        {
+                            Ok(cid) if *last != Some(cid) => {
+                                *last = Some(cid);
+
+                                #[cfg(debug_assertions)]
+                                ConsoleService::info(&format!(
+                                    "IPFS: name update {} \n to {}",
+                                    ipns, cid
+                                ));
+
+                                cb.emit(cid);
+                            }
+                            Ok(_) => {}
+                            Err(IpfsError::Aborted) => return Ok(()),
+                            Err(e) => {
+                                ConsoleService::warn(&format!("IPFS: name watch {} {}", ipns, e))
+                            }
+                        }
+                    }
+                }
+            })
+            .await;
+    }
+
+    /// Publish CID under the IPNS link of this key. Return the IPNS link.
+    pub async fn name_publish<U>(&self, cid: Cid, key: U) -> Result<Cid>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.check_writable()?;
+
+        let key = key.into().into_owned();
+        let timeout = self.timeout.max(PUBLISH_TIMEOUT);
+
+        let ipns = self
+            .cancellable(async {
+                let _permit = self.scheduler.acquire(self.priority).await;
+
+                self.deadline(self.backend.name_publish(cid, key, timeout), timeout)
+                    .await
+            })
+            .await?;
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("IPFS: name publish {} \n to {}", cid, ipns));
+
+        Ok(ipns)
+    }
+
+    /// Upload a file, sending bytes processed to the callback. Return a CID.
+    ///
+    /// Transfers are long lived and do not count toward the request limit.
+    pub async fn add(
+        &self,
+        data: Vec<u8>,
+        options: AddOptions,
+        progress_cb: Callback<u64>,
+    ) -> Result<Cid> {
+        self.check_writable()?;
+
+        let timeout = self.timeout.max(TRANSFER_TIMEOUT);
+
+        let cid = self
+            .cancellable(self.deadline(
+                self.backend.add(data, options, progress_cb, timeout),
+                timeout,
+            ))
+            .await?;
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("IPFS: add => {}", cid));
+
+        Ok(cid)
+    }
+
+    /// Return the number of blocks in the DAG under this CID.
+    ///
+    /// Missing blocks are fetched, like transfers it does not count toward the request limit.
+    pub async fn dag_stat(&self, cid: Cid) -> Result<u64> {
+        self.check_writable()?;
+
+        let timeout = self.timeout.max(TRANSFER_TIMEOUT);
+
+        self.cancellable(self.deadline(self.backend.dag_stat(cid, timeout), timeout))
+            .await
+    }
+
+    /// Pin this CID, sending the number of nodes fetched to the callback.
+    ///
+    /// Transfers are long lived and do not count toward the request limit.
+    pub async fn pin_add(
+        &self,
+        cid: Cid,
+        recursive: bool,
+        progress_cb: Callback<u64>,
+    ) -> Result<()> {
+        self.check_writable()?;
+
+        let timeout = self.timeout.max(TRANSFER_TIMEOUT);
+
+        self.cancellable(self.deadline(
+            self.backend.pin_add(cid, recursive, progress_cb, timeout),
+            timeout,
+        ))
+        .await
+    }
+
+    pub async fn pin_rm(&self, cid: Cid, recursive: bool) -> Result<()> {
+        self.check_writable()?;
+
+        self.cancellable(async {
+            let _permit = self.scheduler.acquire(self.priority).await;
+
+            self.deadline(
+                self.backend.pin_rm(cid, recursive, self.timeout),
+                self.timeout,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Return recursively pinned CIDs.
+    pub async fn pin_ls(&self) -> Result<Vec<Cid>> {
+        self.check_writable()?;
+
+        self.cancellable(async {
+            let _permit = self.scheduler.acquire(self.priority).await;
+
+            self.deadline(self.backend.pin_ls(self.timeout), self.timeout)
+                .await
+        })
+        .await
+    }
+
+    /// Return the daemon's peer ID, switching to read-only mode if it cannot be reached.
+    pub async fn ipfs_node_id(&self) -> Result<PeerId> {
+        let result = self
+            .cancellable(async {
+                let _permit = self.scheduler.acquire(self.priority).await;
+
+                self.deadline(self.backend.ipfs_node_id(), self.timeout)
+                    .await
+            })
+            .await;
+
+        let offline = matches!(
+            result,
+            Err(IpfsError::ConnectionRefused) | Err(IpfsError::Blocked)
+        );
+
+        self.set_read_only(offline);
+
+        result
+    }
+
+    pub async fn pubsub_pub<U>(&self, topic: U, msg: U) -> Result<()>
+    where
+        U: Into<Cow<'static, str>>,
+    {
+        self.check_writable()?;
+
+        let topic = topic.into().into_owned();
+        let msg = msg.into().into_owned();
+
+        self.cancellable(async {
+            let _permit = self.scheduler.acquire(self.priority).await;
+
+            self.deadline(self.backend.pubsub_pub(topic, msg), self.timeout)
+                .await
+        })
+        .await
+    }
+
+    /// Subscribe to a topic then deserialize output.
+    ///
+    /// Dropped streams are resubscribed with exponential backoff, until aborted.
+    /// Subscriptions are long lived and do not count toward the request limit.
+    pub async fn pubsub_sub<U>(
+        &self,
+        topic: U,
+        cb: Callback<Result<(PeerId, Vec<u8>)>>,
+        state_cb: Callback<PubsubState>,
+        regis: AbortRegistration,
+    ) where
+        U: Into<Cow<'static, str>>,
+    {
+        let topic = topic.into().into_owned();
+
+        let opened = Rc::new(Cell::new(false));
+
+        let open_cb = Callback::from({
+            let opened = opened.clone();
+            let state_cb = state_cb.clone();
+            let ipfs = self.clone();
+
+            move |()| {
+                opened.set(true);
+                ipfs.set_read_only(false);
+                state_cb.emit(PubsubState::Connected);
+            }
+        });
+
+        // Resolve when aborted.
+        let mut aborted = Abortable::new(future::pending::<()>(), regis);
+
+        let mut delay = RESUBSCRIBE_DELAY;
+        let mut retries = 0;
+
+        loop {
+            opened.set(false);
+
+            // Aborting the stream instead of dropping it let backends clean up.
+            let (handle, stream_regis) = AbortHandle::new_pair();
+
+            let stream =
+                self.backend
+                    .pubsub_sub(topic.clone(), open_cb.clone(), cb.clone(), stream_regis);
+
+            let result = match future::select(stream, &mut aborted).await {
+                future::Either::Left((result, _)) => result,
+                future::Either::Right((_, stream)) => {
+                    handle.abort();
+                    let _ = stream.await;
+                    return;
+                }
+            };
+
+            if opened.get() {
+                delay = RESUBSCRIBE_DELAY;
+                retries = 0;
+            }
+
+            let error = result
+                .err()
+                .unwrap_or_else(|| IpfsError::Transport("subscription closed".to_owned()));
+
+            if !error.is_retryable() || retries >= MAX_RESUBSCRIBE {
+                state_cb.emit(PubsubState::Failed(error));
+                return;
+            }
+
+            #[cfg(debug_assertions)]
+            ConsoleService::warn(&format!(
+                "IPFS: {} resubscribe to {} in {:?}",
+                error, topic, delay
+            ));
+
+            state_cb.emit(PubsubState::Reconnecting(delay.as_secs()));
+
+            let timer = Box::pin(sleep(delay));
+
+            if let future::Either::Right(_) = future::select(timer, &mut aborted).await {
+                return;
+            }
+
+            delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+            retries += 1;
+        }
+    }
+}
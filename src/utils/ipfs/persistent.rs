@@ -0,0 +1,342 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use js_sys::{Array, Promise, Uint8Array};
+
+use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode};
+
+use yew::services::ConsoleService;
+
+/// Default disk budget of the persistent cache in bytes.
+pub const DEFAULT_PERSISTENT_SIZE: usize = 256 * 1024 * 1024;
+
+const DB_NAME: &str = "ipfs_block_cache";
+const DB_VERSION: u32 = 2;
+const STORE_NAME: &str = "blocks";
+
+/// Maps keys to last use and size of their block.
+const USAGE_STORE: &str = "usage";
+
+/// Block cache persisted in IndexedDB across sessions, bounded by bytes.
+///
+/// Lookups are skipped until the database is open.
+pub struct PersistentCache {
+    db: RefCell<Option<IdbDatabase>>,
+    usage: RefCell<Usage>,
+}
+
+impl PersistentCache {
+    pub fn new(capacity: usize) -> Rc<Self> {
+        let cache = Rc::new(Self {
+            db: RefCell::new(None),
+            usage: RefCell::new(Usage::new(capacity)),
+        });
+
+        spawn_local({
+            let cache = cache.clone();
+
+            async move {
+                let db = match open_database().await {
+                    Ok(db) => db,
+                    Err(e) => {
+                        ConsoleService::error(&format!("{:?}", e));
+                        return;
+                    }
+                };
+
+                let mut usage = Usage::new(capacity);
+
+                if let Err(e) = usage.load(&db).await {
+                    ConsoleService::error(&format!("{:?}", e));
+                    return;
+                }
+
+                *cache.usage.borrow_mut() = usage;
+                *cache.db.borrow_mut() = Some(db);
+            }
+        });
+
+        cache
+    }
+
+    pub async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let request = {
+            let db = self.db.borrow();
+            let db = db.as_ref()?;
+
+            let (last_use, size) = self.usage.borrow_mut().touch(key)?;
+
+            let result = db
+                .transaction_with_str_and_mode(USAGE_STORE, IdbTransactionMode::Readwrite)
+                .and_then(|tx| tx.object_store(USAGE_STORE))
+                .and_then(|store| {
+                    store.put_with_key(&usage_value(last_use, size), &JsValue::from_str(key))
+                });
+
+            if let Err(e) = result {
+                ConsoleService::error(&format!("{:?}", e));
+            }
+
+            let request = db
+                .transaction_with_str(STORE_NAME)
+                .and_then(|tx| tx.object_store(STORE_NAME))
+                .and_then(|store| store.get(&JsValue::from_str(key)));
+
+            match request {
+                Ok(request) => request,
+                Err(e) => {
+                    ConsoleService::error(&format!("{:?}", e));
+                    return None;
+                }
+            }
+        };
+
+        if let Err(e) = request_future(&request).await {
+            ConsoleService::error(&format!("{:?}", e));
+            return None;
+        }
+
+        let value = request.result().ok()?;
+
+        if value.is_undefined() {
+            return None;
+        }
+
+        Some(Uint8Array::new(&value).to_vec())
+    }
+
+    pub async fn insert(&self, key: &str, data: &[u8]) {
+        let request = {
+            let db = self.db.borrow();
+            let db = match db.as_ref() {
+                Some(db) => db,
+                None => return,
+            };
+
+            let (last_use, evicted) = match self.usage.borrow_mut().insert(key, data.len()) {
+                Some(result) => result,
+                None => return,
+            };
+
+            let stores = Array::of2(
+                &JsValue::from_str(STORE_NAME),
+                &JsValue::from_str(USAGE_STORE),
+            );
+
+            let request = db
+                .transaction_with_str_sequence_and_mode(&stores, IdbTransactionMode::Readwrite)
+                .and_then(|tx| {
+                    let blocks = tx.object_store(STORE_NAME)?;
+                    let usage = tx.object_store(USAGE_STORE)?;
+
+                    for old_key in evicted.iter() {
+                        let old_key = JsValue::from_str(old_key);
+
+                        blocks.delete(&old_key)?;
+                        usage.delete(&old_key)?;
+                    }
+
+                    let key = JsValue::from_str(key);
+
+                    usage.put_with_key(&usage_value(last_use, data.len()), &key)?;
+
+                    blocks.put_with_key(&Uint8Array::from(data), &key)
+                });
+
+            match request {
+                Ok(request) => request,
+                Err(e) => {
+                    ConsoleService::error(&format!("{:?}", e));
+                    return;
+                }
+            }
+        };
+
+        if let Err(e) = request_future(&request).await {
+            ConsoleService::error(&format!("{:?}", e));
+        }
+    }
+}
+
+async fn open_database() -> Result<IdbDatabase, JsValue> {
+    let factory = match web_sys::window() {
+        Some(window) => window.indexed_db()?,
+        None => None,
+    };
+
+    let factory = match factory {
+        Some(factory) => factory,
+        None => return Err(JsValue::from_str("No IndexedDB")),
+    };
+
+    let request = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+
+    let upgrade_closure = Closure::wrap(Box::new({
+        let request = request.clone();
+
+        move || {
+            let result = request
+                .result()
+                .and_then(|db| db.dyn_into::<IdbDatabase>())
+                .and_then(|db| {
+                    // The cache starts empty on every version change, stores may not exist yet.
+                    let _ = db.delete_object_store(STORE_NAME);
+                    let _ = db.delete_object_store(USAGE_STORE);
+
+                    db.create_object_store(STORE_NAME)?;
+                    db.create_object_store(USAGE_STORE)
+                });
+
+            if let Err(e) = result {
+                ConsoleService::error(&format!("{:?}", e));
+            }
+        }
+    }) as Box<dyn Fn()>);
+
+    request.set_onupgradeneeded(Some(upgrade_closure.as_ref().unchecked_ref()));
+
+    let result = request_future(&request).await;
+
+    request.set_onupgradeneeded(None);
+
+    result?;
+
+    let db = request.result()?.dyn_into::<IdbDatabase>()?;
+
+    Ok(db)
+}
+
+/// Usage record of one block, `[last use, size]`.
+fn usage_value(last_use: u64, size: usize) -> JsValue {
+    Array::of2(&(last_use as f64).into(), &(size as f64).into()).into()
+}
+
+/// In memory copy of the usage store, used to pick blocks to evict.
+struct Usage {
+    capacity: usize,
+    size: usize,
+
+    tick: u64,
+
+    /// Maps key to last use and size
+    entries: HashMap<String, (u64, usize)>,
+
+    /// Maps last use to key
+    order: BTreeMap<u64, String>,
+}
+
+impl Usage {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+
+            tick: 0,
+
+            entries: HashMap::with_capacity(100),
+            order: BTreeMap::new(),
+        }
+    }
+
+    /// Read every usage record from the database.
+    async fn load(&mut self, db: &IdbDatabase) -> Result<(), JsValue> {
+        let store = db
+            .transaction_with_str(USAGE_STORE)?
+            .object_store(USAGE_STORE)?;
+
+        let keys_request = store.get_all_keys()?;
+        let values_request = store.get_all()?;
+
+        request_future(&keys_request).await?;
+        request_future(&values_request).await?;
+
+        let keys = Array::from(&keys_request.result()?);
+        let values = Array::from(&values_request.result()?);
+
+        for (key, value) in keys.iter().zip(values.iter()) {
+            let key = match key.as_string() {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let value = Array::from(&value);
+
+            let (last_use, size) = match (value.get(0).as_f64(), value.get(1).as_f64()) {
+                (Some(last_use), Some(size)) => (last_use as u64, size as usize),
+                _ => continue,
+            };
+
+            self.tick = self.tick.max(last_use);
+            self.size += size;
+
+            self.order.insert(last_use, key.clone());
+            self.entries.insert(key, (last_use, size));
+        }
+
+        Ok(())
+    }
+
+    /// Mark the key as used, returning its new last use and size.
+    fn touch(&mut self, key: &str) -> Option<(u64, usize)> {
+        let (last_use, size) = self.entries.get_mut(key)?;
+
+        self.tick += 1;
+
+        if let Some(key) = self.order.remove(&*last_use) {
+            self.order.insert(self.tick, key);
+        }
+
+        *last_use = self.tick;
+
+        Some((self.tick, *size))
+    }
+
+    /// Track a new block, returning its last use and the keys to evict.
+    fn insert(&mut self, key: &str, size: usize) -> Option<(u64, Vec<String>)> {
+        if size > self.capacity || self.entries.contains_key(key) {
+            return None;
+        }
+
+        self.size += size;
+
+        let mut evicted = Vec::new();
+
+        while self.size > self.capacity {
+            let oldest = match self.order.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+
+            if let Some(old_key) = self.order.remove(&oldest) {
+                if let Some((_, old_size)) = self.entries.remove(&old_key) {
+                    self.size -= old_size;
+                }
+
+                evicted.push(old_key);
+            }
+        }
+
+        self.tick += 1;
+
+        self.order.insert(self.tick, key.to_owned());
+        self.entries.insert(key.to_owned(), (self.tick, size));
+
+        Some((self.tick, evicted))
+    }
+}
+
+/// Resolve when the request succeed, reject when it fails.
+fn request_future(request: &IdbRequest) -> JsFuture {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+
+    JsFuture::from(promise)
+}
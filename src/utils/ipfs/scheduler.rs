@@ -0,0 +1,94 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+};
+
+use crate::utils::ipfs::slot::{Receiver, Slot};
+
+/// Default number of concurrent requests, the browser connection limit per host.
+pub const DEFAULT_MAX_REQUESTS: usize = 6;
+
+/// Requests with higher priority are sent first when the limit is reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Thumbnails, comments and other content below the fold.
+    Low = 0,
+    Normal = 1,
+    /// Video playback.
+    High = 2,
+}
+
+struct Inner {
+    limit: usize,
+    running: Cell<usize>,
+
+    /// One queue per priority, indexed by priority.
+    queues: RefCell<[VecDeque<Rc<Slot<Permit>>>; 3]>,
+}
+
+/// Limit the number of concurrent requests.
+#[derive(Clone)]
+pub struct Scheduler {
+    inner: Rc<Inner>,
+}
+
+impl Scheduler {
+    pub fn new(limit: usize) -> Self {
+        let inner = Inner {
+            limit: limit.max(1),
+            running: Cell::new(0),
+            queues: RefCell::new([VecDeque::new(), VecDeque::new(), VecDeque::new()]),
+        };
+
+        Self {
+            inner: Rc::new(inner),
+        }
+    }
+
+    /// Wait for a free slot. The slot is released when the permit is dropped.
+    pub async fn acquire(&self, priority: Priority) -> Permit {
+        let running = self.inner.running.get();
+
+        if running < self.inner.limit {
+            self.inner.running.set(running + 1);
+
+            return Permit {
+                inner: self.inner.clone(),
+            };
+        }
+
+        let slot = Slot::new();
+
+        self.inner.queues.borrow_mut()[priority as usize].push_back(slot.clone());
+
+        Receiver(slot).await
+    }
+}
+
+/// Right to send one request.
+pub struct Permit {
+    inner: Rc<Inner>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut queues = self.inner.queues.borrow_mut();
+
+        for queue in queues.iter_mut().rev() {
+            while let Some(slot) = queue.pop_front() {
+                // Skip requests dropped while waiting.
+                if Rc::strong_count(&slot) > 1 {
+                    slot.send(Permit {
+                        inner: self.inner.clone(),
+                    });
+
+                    return;
+                }
+            }
+        }
+
+        let running = self.inner.running.get();
+        self.inner.running.set(running.saturating_sub(1));
+    }
+}
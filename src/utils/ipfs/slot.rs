@@ -0,0 +1,52 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Value handed once from one task to another on the same thread.
+pub struct Slot<T> {
+    value: RefCell<Option<T>>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl<T> Slot<T> {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self {
+            value: RefCell::new(None),
+            waker: RefCell::new(None),
+        })
+    }
+
+    pub fn send(&self, value: T) {
+        *self.value.borrow_mut() = Some(value);
+
+        let waker = self.waker.borrow_mut().take();
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolve when a value is sent to the slot.
+pub struct Receiver<T>(pub Rc<Slot<T>>);
+
+impl<T> Future for Receiver<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let value = self.0.value.borrow_mut().take();
+
+        match value {
+            Some(value) => Poll::Ready(value),
+            None => {
+                *self.0.waker.borrow_mut() = Some(cx.waker().clone());
+
+                Poll::Pending
+            }
+        }
+    }
+}
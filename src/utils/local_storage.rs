@@ -6,7 +6,22 @@ use yew::services::ConsoleService;
 
 use cid::Cid;
 
+use serde::{Deserialize, Serialize};
+
 const IPFS_API_ADDRS_KEY: &str = "ipfs_api_addrs";
+const IPFS_GATEWAYS_KEY: &str = "ipfs_gateways";
+const PERSISTENT_CACHE_KEY: &str = "ipfs_persistent_cache";
+const MAX_REQUESTS_KEY: &str = "ipfs_max_requests";
+const SEEDED_KEY: &str = "ipfs_seeded";
+const CHAT_HISTORY_KEY: &str = "chat_history";
+const CHAT_NOTIFY_KEY: &str = "chat_notify";
+
+/// Content pinned from this app.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Seeded {
+    pub cid: String,
+    pub title: String,
+}
 
 #[derive(Clone)]
 pub struct LocalStorage {
@@ -109,4 +124,181 @@ impl LocalStorage {
 
         Some(addrs)
     }
+
+    pub fn set_gateways(&self, gateways: &[String]) {
+        let value = match serde_json::to_string(gateways) {
+            Ok(value) => value,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return;
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Storage Set => {} \n {}",
+            IPFS_GATEWAYS_KEY, &value
+        ));
+
+        if let Err(e) = self.storage.set_item(IPFS_GATEWAYS_KEY, &value) {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    pub fn get_gateways(&self) -> Option<Vec<String>> {
+        let value = match self.storage.get_item(IPFS_GATEWAYS_KEY) {
+            Ok(option) => option?,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return None;
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Storage Get => {} \n {}",
+            IPFS_GATEWAYS_KEY, &value
+        ));
+
+        match serde_json::from_str(&value) {
+            Ok(gateways) => Some(gateways),
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                None
+            }
+        }
+    }
+
+    pub fn set_persistent_cache(&self, enabled: bool) {
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Storage Set => {} \n {}",
+            PERSISTENT_CACHE_KEY, enabled
+        ));
+
+        if let Err(e) = self
+            .storage
+            .set_item(PERSISTENT_CACHE_KEY, &enabled.to_string())
+        {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    /// Persistent cache is disabled by default.
+    pub fn get_persistent_cache(&self) -> bool {
+        match self.storage.get_item(PERSISTENT_CACHE_KEY) {
+            Ok(option) => option.as_deref() == Some("true"),
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                false
+            }
+        }
+    }
+
+    pub fn set_max_requests(&self, max: usize) {
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("Storage Set => {} \n {}", MAX_REQUESTS_KEY, max));
+
+        if let Err(e) = self.storage.set_item(MAX_REQUESTS_KEY, &max.to_string()) {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    pub fn get_max_requests(&self) -> Option<usize> {
+        let max = match self.storage.get_item(MAX_REQUESTS_KEY) {
+            Ok(option) => option?,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return None;
+            }
+        };
+
+        max.parse().ok()
+    }
+
+    pub fn set_chat_history(&self, length: usize) {
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Storage Set => {} \n {}",
+            CHAT_HISTORY_KEY, length
+        ));
+
+        if let Err(e) = self.storage.set_item(CHAT_HISTORY_KEY, &length.to_string()) {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    pub fn get_chat_history(&self) -> Option<usize> {
+        let length = match self.storage.get_item(CHAT_HISTORY_KEY) {
+            Ok(option) => option?,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return None;
+            }
+        };
+
+        length.parse().ok()
+    }
+
+    pub fn set_chat_notify(&self, enabled: bool) {
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!(
+            "Storage Set => {} \n {}",
+            CHAT_NOTIFY_KEY, enabled
+        ));
+
+        if let Err(e) = self.storage.set_item(CHAT_NOTIFY_KEY, &enabled.to_string()) {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    /// Mention notifications are disabled by default.
+    pub fn get_chat_notify(&self) -> bool {
+        match self.storage.get_item(CHAT_NOTIFY_KEY) {
+            Ok(option) => option.as_deref() == Some("true"),
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                false
+            }
+        }
+    }
+
+    pub fn set_seeded(&self, seeded: &[Seeded]) {
+        let value = match serde_json::to_string(seeded) {
+            Ok(value) => value,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return;
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("Storage Set => {} \n {}", SEEDED_KEY, &value));
+
+        if let Err(e) = self.storage.set_item(SEEDED_KEY, &value) {
+            ConsoleService::error(&format!("{:#?}", e));
+        }
+    }
+
+    pub fn get_seeded(&self) -> Vec<Seeded> {
+        let value = match self.storage.get_item(SEEDED_KEY) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                return Vec::new();
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        ConsoleService::info(&format!("Storage Get => {} \n {}", SEEDED_KEY, &value));
+
+        match serde_json::from_str(&value) {
+            Ok(seeded) => seeded,
+            Err(e) => {
+                ConsoleService::error(&format!("{:#?}", e));
+                Vec::new()
+            }
+        }
+    }
 }
@@ -1,5 +1,9 @@
 /// Original author of this code is [Nathan Ringo](https://github.com/remexre)
 /// Source: https://github.com/acmumn/mentoring/blob/master/web-client/src/view/markdown.rs
+use std::convert::TryFrom;
+
+use crate::app::AppRoute;
+
 use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag};
 
 use yew::{
@@ -8,6 +12,12 @@ use yew::{
     Classes, Html,
 };
 
+use yew_router::components::RouterAnchor;
+
+use cid::Cid;
+
+type Anchor = RouterAnchor<AppRoute>;
+
 /// Adds a class to the VTag.
 /// You can also provide multiple classes separated by ascii whitespaces.
 ///
@@ -187,3 +197,395 @@ fn make_tag(t: Tag) -> VTag {
         }
     }
 }
+
+/// Frame of the inline element stack used when rendering chat messages.
+enum Frame {
+    Tag(VTag),
+    /// Link to content on this app.
+    Route(Cid, Vec<VNode>),
+    /// Disallowed element, its text goes to the parent.
+    Skip,
+}
+
+/// Renders a chat message with inline Markdown only (bold, italic, strikethrough, code and links).
+///
+/// Images and block elements are never produced, their text is kept.
+/// CIDs and ipfs:// links point to the content page.
+/// Words like `:code:` are replaced by the emote callback, when it returns something.
+pub fn render_chat_markdown(src: &str, emote: &dyn Fn(&str) -> Option<Html>) -> Html {
+    let mut root = VTag::new("span");
+    let mut stack: Vec<Frame> = Vec::new();
+
+    // The parser may split text on delimiters, join it back so words stay whole.
+    let mut text = String::new();
+
+    for ev in Parser::new_ext(src, Options::ENABLE_STRIKETHROUGH) {
+        if let Event::Text(ref piece) | Event::Html(ref piece) = ev {
+            text.push_str(piece);
+            continue;
+        }
+
+        if !text.is_empty() {
+            add_text(&mut stack, &mut root, &std::mem::take(&mut text), emote);
+        }
+
+        match ev {
+            Event::Start(tag) => stack.push(make_inline_frame(tag)),
+            Event::End(tag) => {
+                let node = match stack.pop() {
+                    Some(Frame::Tag(vtag)) => VNode::VTag(Box::new(vtag)),
+                    Some(Frame::Route(cid, children)) => html! {
+                        <Anchor route=AppRoute::Content(cid) >
+                            { for children.into_iter() }
+                        </Anchor>
+                    },
+                    Some(Frame::Skip) | None => {
+                        // Paragraphs are now inline, keep them apart.
+                        if let Tag::Paragraph = tag {
+                            add_inline_child(&mut stack, &mut root, VText::new(" ").into());
+                        }
+
+                        continue;
+                    }
+                };
+
+                add_inline_child(&mut stack, &mut root, node);
+            }
+            Event::Code(code) => {
+                let mut el = VTag::new("code");
+                el.add_child(VText::new(code.to_string()).into());
+
+                add_inline_child(&mut stack, &mut root, el.into());
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                add_inline_child(&mut stack, &mut root, VText::new(" ").into())
+            }
+            _ => {}
+        }
+    }
+
+    if !text.is_empty() {
+        add_text(&mut stack, &mut root, &text, emote);
+    }
+
+    VNode::VTag(Box::new(root))
+}
+
+fn add_text(
+    stack: &mut [Frame],
+    root: &mut VTag,
+    text: &str,
+    emote: &dyn Fn(&str) -> Option<Html>,
+) {
+    if in_link(stack) {
+        add_inline_child(stack, root, VText::new(text.to_owned()).into());
+    } else {
+        add_linked_text(stack, root, text, emote);
+    }
+}
+
+fn make_inline_frame(t: Tag) -> Frame {
+    match t {
+        Tag::Emphasis => {
+            let mut el = VTag::new("em");
+            el.add_attribute("class", "is-italic");
+            Frame::Tag(el)
+        }
+        Tag::Strong => {
+            let mut el = VTag::new("strong");
+            el.add_attribute("class", "has-text-weight-bold");
+            Frame::Tag(el)
+        }
+        Tag::Strikethrough => Frame::Tag(VTag::new("del")),
+        Tag::Link(_link_type, ref href, ref _title) => {
+            if let Some(cid) = content_cid(href) {
+                return Frame::Route(cid, Vec::new());
+            }
+
+            if !href.starts_with("https://") && !href.starts_with("http://") {
+                return Frame::Skip;
+            }
+
+            let mut el = VTag::new("a");
+            el.add_attribute("href", href.to_string());
+            el.add_attribute("target", "_blank");
+            el.add_attribute("rel", "noopener noreferrer nofollow");
+            Frame::Tag(el)
+        }
+        _ => Frame::Skip,
+    }
+}
+
+/// Add to the closest allowed element.
+fn add_inline_child(stack: &mut [Frame], root: &mut VTag, node: VNode) {
+    for frame in stack.iter_mut().rev() {
+        match frame {
+            Frame::Tag(vtag) => return vtag.add_child(node),
+            Frame::Route(_, children) => return children.push(node),
+            Frame::Skip => continue,
+        }
+    }
+
+    root.add_child(node);
+}
+
+fn in_link(stack: &[Frame]) -> bool {
+    stack.iter().any(|frame| match frame {
+        Frame::Route(_, _) => true,
+        Frame::Tag(vtag) => vtag.tag() == "a",
+        Frame::Skip => false,
+    })
+}
+
+/// Add text, turning CIDs into links to the content page and codes into emotes.
+fn add_linked_text(
+    stack: &mut [Frame],
+    root: &mut VTag,
+    text: &str,
+    emote: &dyn Fn(&str) -> Option<Html>,
+) {
+    let mut buffer = String::new();
+
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end();
+
+        let node = word
+            .strip_prefix(':')
+            .and_then(|code| code.strip_suffix(':'))
+            .filter(|code| !code.is_empty())
+            .and_then(|code| emote(code));
+
+        if let Some(node) = node {
+            if !buffer.is_empty() {
+                add_inline_child(stack, root, VText::new(std::mem::take(&mut buffer)).into());
+            }
+
+            add_inline_child(stack, root, node);
+
+            buffer.push_str(&piece[word.len()..]);
+            continue;
+        }
+
+        let word =
+            word.trim_end_matches(|c: char| matches!(c, '.' | ',' | '!' | '?' | ')' | ':' | ';'));
+
+        let cid = match content_cid(word) {
+            Some(cid) => cid,
+            None => {
+                buffer.push_str(piece);
+                continue;
+            }
+        };
+
+        if !buffer.is_empty() {
+            add_inline_child(stack, root, VText::new(std::mem::take(&mut buffer)).into());
+        }
+
+        let link = html! {
+            <Anchor route=AppRoute::Content(cid) >
+                { word }
+            </Anchor>
+        };
+
+        add_inline_child(stack, root, link);
+
+        buffer.push_str(&piece[word.len()..]);
+    }
+
+    if !buffer.is_empty() {
+        add_inline_child(stack, root, VText::new(buffer).into());
+    }
+}
+
+/// Parse ipfs://CID, /ipfs/CID or a bare CID, paths are ignored.
+fn content_cid(text: &str) -> Option<Cid> {
+    let text = text
+        .strip_prefix("ipfs://")
+        .or_else(|| text.strip_prefix("/ipfs/"))
+        .unwrap_or(text);
+
+    let cid = text.split('/').next()?;
+
+    // Shortest CIDs are 46 characters, avoid parsing every word.
+    if cid.len() < 46 {
+        return None;
+    }
+
+    Cid::try_from(cid).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const CID: &str = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+
+    /// Elements, attributes and text of a rendered message.
+    #[derive(Default)]
+    struct Rendered {
+        tags: Vec<(String, Vec<(String, String)>)>,
+        routes: usize,
+        text: String,
+    }
+
+    impl Rendered {
+        fn new(src: &str) -> Self {
+            let mut rendered = Self::default();
+
+            rendered.walk(&render_chat_markdown(src, &|code| match code {
+                "wave" => Some(html! { <img src="wave.png" /> }),
+                _ => None,
+            }));
+
+            rendered
+        }
+
+        fn walk(&mut self, node: &VNode) {
+            match node {
+                VNode::VTag(vtag) => {
+                    let attributes = vtag
+                        .attributes
+                        .iter()
+                        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                        .collect();
+
+                    self.tags.push((vtag.tag().to_owned(), attributes));
+
+                    for child in vtag.children.iter() {
+                        self.walk(child);
+                    }
+                }
+                VNode::VText(vtext) => self.text.push_str(&vtext.text),
+                VNode::VComp(_) => self.routes += 1,
+                VNode::VList(list) => {
+                    for child in list.iter() {
+                        self.walk(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn has_tag(&self, name: &str) -> bool {
+            self.tags.iter().any(|(tag, _)| tag == name)
+        }
+
+        fn hrefs(&self) -> Vec<&str> {
+            self.tags
+                .iter()
+                .flat_map(|(_, attributes)| attributes.iter())
+                .filter(|(k, _)| k == "href")
+                .map(|(_, v)| v.as_str())
+                .collect()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn web_links_open_in_new_tab() {
+        let rendered = Rendered::new("[site](https://example.com) [old](http://example.com)");
+
+        assert_eq!(
+            rendered.hrefs(),
+            vec!["https://example.com", "http://example.com"]
+        );
+
+        let (_, attributes) = &rendered.tags[1];
+
+        assert!(attributes.contains(&("target".to_owned(), "_blank".to_owned())));
+        assert!(attributes.contains(&("rel".to_owned(), "noopener noreferrer nofollow".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    fn other_schemes_are_not_linked() {
+        for src in &[
+            "[click](javascript:alert(1))",
+            "[click](JavaScript:alert(1))",
+            "[click](data:text/html;base64,PHNjcmlwdD4=)",
+            "[click](vbscript:msgbox)",
+            "<javascript:alert(1)>",
+            "[click](//evil.example)",
+        ] {
+            let rendered = Rendered::new(src);
+
+            assert!(!rendered.has_tag("a"), "{} produced a link", src);
+            assert_eq!(rendered.routes, 0, "{} produced a route", src);
+        }
+
+        assert_eq!(
+            Rendered::new("[click](javascript:alert(1))")
+                .text
+                .trim_end(),
+            "click"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn raw_html_is_text() {
+        let rendered = Rendered::new("<script>alert(1)</script> <img src=x onerror=alert(1)>");
+
+        assert!(!rendered.has_tag("script"));
+        assert!(!rendered.has_tag("img"));
+        assert!(rendered.text.contains("<script>"));
+    }
+
+    #[wasm_bindgen_test]
+    fn images_and_blocks_keep_their_text() {
+        let rendered = Rendered::new("# Title\n\n![alt](https://example.com/x.png)");
+
+        assert!(!rendered.has_tag("h1"));
+        assert!(!rendered.has_tag("img"));
+        assert!(rendered.text.contains("Title"));
+        assert!(rendered.text.contains("alt"));
+    }
+
+    #[wasm_bindgen_test]
+    fn inline_markdown() {
+        let rendered = Rendered::new("**bold** *italic* ~~gone~~ `code`");
+
+        assert!(rendered.has_tag("strong"));
+        assert!(rendered.has_tag("em"));
+        assert!(rendered.has_tag("del"));
+        assert!(rendered.has_tag("code"));
+    }
+
+    #[wasm_bindgen_test]
+    fn cids_link_to_content() {
+        for src in &[
+            format!("look {}.", CID),
+            format!("ipfs://{}/path", CID),
+            format!("/ipfs/{}", CID),
+            format!("[this post](ipfs://{})", CID),
+        ] {
+            let rendered = Rendered::new(src);
+
+            assert_eq!(rendered.routes, 1, "{} was not linked", src);
+            assert!(!rendered.has_tag("a"));
+        }
+
+        let rendered = Rendered::new(&format!("look {}.", CID));
+
+        assert_eq!(rendered.text.trim_end(), "look .");
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_cids_stay_text() {
+        let src = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzd1";
+        let rendered = Rendered::new(src);
+
+        assert_eq!(rendered.routes, 0);
+        assert_eq!(rendered.text.trim_end(), src);
+    }
+
+    #[wasm_bindgen_test]
+    fn known_emotes_are_replaced() {
+        let rendered = Rendered::new("hi :wave: :unknown:");
+
+        assert_eq!(
+            rendered.tags.iter().filter(|(tag, _)| tag == "img").count(),
+            1
+        );
+        assert_eq!(rendered.text.trim_end(), "hi  :unknown:");
+    }
+}
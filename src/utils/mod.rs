@@ -2,13 +2,18 @@ mod ema;
 mod ipfs;
 mod local_storage;
 mod markdown;
+mod timer;
 mod web3;
 
 pub use self::web3::Web3Service;
 pub use ema::ExponentialMovingAverage;
-pub use ipfs::{IpfsService, DEFAULT_URI};
-pub use local_storage::LocalStorage;
-pub use markdown::render_markdown;
+pub use ipfs::{
+    gateway_url, test_gateway, AddOptions, CancelToken, CatProgress, IpfsError, IpfsService,
+    Priority, PubsubState, DEFAULT_GATEWAYS, DEFAULT_MAX_REQUESTS, DEFAULT_URI,
+};
+pub use local_storage::{LocalStorage, Seeded};
+pub use markdown::{render_chat_markdown, render_markdown};
+pub use timer::sleep;
 
 /// Translate total number of seconds to timecode.
 pub fn seconds_to_timecode(seconds: f64) -> (u8, u8, u8) {
@@ -36,6 +41,11 @@ pub fn timestamp_to_datetime(seconds: u64) -> String {
     local_d_t.format("%Y-%m-%d %H:%M:%S").to_string()
 }
 
+/// Current unix time in total number of seconds.
+pub fn current_timestamp() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
 use yew::prelude::{classes, html, Html};
 
 pub fn feature_card(title: &str, text: &str) -> Html {
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use wasm_bindgen_futures::JsFuture;
+
+use js_sys::Promise;
+
+use yew::services::ConsoleService;
+
+/// Resolve after this duration.
+pub async fn sleep(duration: Duration) {
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => {
+                ConsoleService::error("No Window Object");
+                return;
+            }
+        };
+
+        if let Err(e) = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            &resolve,
+            duration.as_millis() as i32,
+        ) {
+            ConsoleService::error(&format!("{:?}", e));
+        }
+    });
+
+    if let Err(e) = JsFuture::from(promise).await {
+        ConsoleService::error(&format!("{:?}", e));
+    }
+}
@@ -102,6 +102,29 @@ impl Web3Service {
         Ok(sign.to_fixed_bytes())
     }
 
+    /// Resolve the address of the ENS name `<name>.eth`, names are the same as beacons'.
+    pub async fn name_address(&self, name: &str) -> Result<Address> {
+        let client = match &self.client {
+            Some(clt) => clt,
+            None => return Err(NoWeb3.into()),
+        };
+
+        let name = &format!("{}.eth", name);
+
+        let address = client.ens().get_eth_address(name).await?;
+
+        Ok(address)
+    }
+
+    /// Find the beacon of this address through its primary ENS name.
+    pub async fn address_beacon(&self, addrs: Address) -> Result<Cid> {
+        let name = self.reverse_resolve(addrs).await?;
+
+        let name = name.trim_end_matches(".eth").to_owned();
+
+        self.get_ipfs_content(name).await
+    }
+
     //https://eips.ethereum.org/EIPS/eip-181
     pub async fn reverse_resolve(&self, addrs: Address) -> Result<String> {
         let client = match &self.client {